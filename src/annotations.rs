@@ -0,0 +1,147 @@
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDateTime;
+use log::debug;
+use std::io::{self, BufRead};
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::parameters::Parameters;
+use crate::plotter::Rgb;
+
+// Reference lines and events drawn on the metric plots, written in the annotations file as:
+//     line: Processor(_Total)\% User Time | 80 | SLO | red
+//     event: 05/18/2021 10:05:00 | Deploy
+// Events can also come from an events file, with one <time>,<label> line per event.
+pub struct Annotations {
+    lines: Vec<ReferenceLine>,
+    events: Vec<Event>
+}
+
+// Horizontal line on every metric that contains the pattern
+#[derive(Debug)]
+pub struct ReferenceLine {
+    pattern: String,
+    pub value: f64,
+    pub label: String,
+    pub colour: Rgb
+}
+
+// Vertical line at a moment of the capture
+#[derive(Debug)]
+pub struct Event {
+    pub time: EventTime,
+    pub label: String,
+    pub colour: Rgb
+}
+
+#[derive(Debug)]
+pub enum EventTime {
+    // Seconds since the first sample of each file
+    Elapsed(f64),
+    // Timestamp in the data time format
+    At(NaiveDateTime)
+}
+
+impl Annotations {
+
+    pub fn load(param: &Parameters) -> Result<Annotations> {
+        let mut annotations = Annotations { lines: Vec::new(), events: Vec::new() };
+        annotations.read_annotations_file(&param.annotations_file, param)?;
+        if let Some(events_file) = &param.events_file {
+            annotations.read_events_file(events_file, param)?;
+        }
+
+        debug!("Reference lines: {:?}", annotations.lines);
+        debug!("Events: {:?}", annotations.events);
+        Ok(annotations)
+    }
+
+    pub fn get_lines(&self, metric: &str) -> Vec<&ReferenceLine> {
+        self.lines.iter()
+            .filter(|line| metric.contains(line.pattern.as_str()))
+            .collect()
+    }
+
+    pub fn get_events(&self) -> &Vec<Event> {
+        &self.events
+    }
+
+    fn read_annotations_file(&mut self, annotations_location: &PathBuf, param: &Parameters) -> Result<()> {
+        let file = File::open(annotations_location)
+            .with_context(|| format!("Could not open file {:?}", annotations_location))?;
+        let reader = io::BufReader::new(file).lines();
+
+        for line in reader.map_while(Result::ok) {
+            if line.trim().is_empty() || line.trim_start().starts_with("//") {
+                continue;
+            }
+
+            // Split only once, event times contain ':'
+            let config: Vec<&str> = line.splitn(2, ':').map(|s| s.trim()).collect();
+            if config.len() != 2 {
+                bail!("Annotation line {:?} should be written as <key: value>", line);
+            }
+            let fields: Vec<&str> = config[1].split('|').map(|s| s.trim()).collect();
+
+            match (config[0], fields.len()) {
+                ("line", 2..=4) => self.lines.push(ReferenceLine {
+                    pattern: fields[0].to_string(),
+                    value: fields[1].parse::<f64>()
+                        .with_context(|| format!("Invalid reference line value {:?}", fields[1]))?,
+                    label: fields.get(2).unwrap_or(&"Reference").to_string(),
+                    colour: parse_colour(fields.get(3), Rgb(0, 0, 0))?
+                }),
+                ("line", _) => bail!("Reference line {:?} should be written as <line: metric | value | label | colour>, the label and colour are optional", line),
+                ("event", 1..=3) => self.events.push(Event {
+                    time: parse_event_time(fields[0], param)?,
+                    label: fields.get(1).unwrap_or(&"Event").to_string(),
+                    colour: parse_colour(fields.get(2), Rgb(100, 100, 100))?
+                }),
+                ("event", _) => bail!("Event {:?} should be written as <event: time | label | colour>, the label and colour are optional", line),
+                _ => bail!("Unknown annotation {}. Options are <line> or <event>", config[0])
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_events_file(&mut self, events_location: &PathBuf, param: &Parameters) -> Result<()> {
+        let file = File::open(events_location)
+            .with_context(|| format!("Could not open file {:?}", events_location))?;
+        let reader = io::BufReader::new(file).lines();
+
+        for line in reader.map_while(Result::ok) {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.splitn(2, ',').map(|s| s.trim().trim_matches('"')).collect();
+            self.events.push(Event {
+                time: parse_event_time(fields[0], param)
+                    .with_context(|| format!("Problem in the events file {:?}", events_location))?,
+                label: fields.get(1).unwrap_or(&"Event").to_string(),
+                colour: Rgb(100, 100, 100)
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Plain numbers are seconds since the start of each file, anything else a timestamp
+fn parse_event_time(time: &str, param: &Parameters) -> Result<EventTime> {
+    if let Ok(seconds) = time.parse::<f64>() {
+        return Ok(EventTime::Elapsed(seconds));
+    }
+
+    let timestamp = NaiveDateTime::parse_from_str(time, &param.data_time_format)
+        .with_context(|| format!("Event time {:?} is neither seconds nor a timestamp in the format {}", time, param.data_time_format))?;
+    Ok(EventTime::At(timestamp))
+}
+
+fn parse_colour(colour: Option<&&str>, default: Rgb) -> Result<Rgb> {
+    match colour {
+        Some(colour) => colour.parse::<Rgb>().with_context(|| format!("Invalid annotation colour {:?}", colour)),
+        None => Ok(default)
+    }
+}
@@ -0,0 +1,111 @@
+use serde::Serialize;
+
+use crate::metric_options::{AnomalyDetector, MetricOptions};
+
+// Lowest spread a window can have, as a share of the standard deviation of the whole metric and
+// of the mean of the window. Without it, a counter that sits flat and then moves by one unit
+// would get an infinite score.
+static MIN_SPREAD_SHARE_OF_DEVIATION: f64 = 0.5;
+static MIN_SPREAD_SHARE_OF_MEAN: f64 = 0.01;
+
+// Consecutive flagged samples, in seconds since the first timestamp of the file
+#[derive(Serialize, Debug, Clone)]
+pub struct Anomaly {
+    pub start: f64,
+    pub end: f64,
+    // Value of the sample with the highest score
+    pub peak_value: f64,
+    pub score: f64
+}
+
+pub fn detect_anomalies(seconds: &[f64], values: &[f64], options: &MetricOptions) -> Vec<Anomaly> {
+    let window = options.anomaly_window.max(2);
+    if values.len() <= window {
+        return Vec::new();
+    }
+
+    let amount = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / amount;
+    let deviation = (values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (amount - 1.0)).sqrt();
+    let min_spread = MIN_SPREAD_SHARE_OF_DEVIATION * deviation;
+
+    let scores: Vec<f64> = match options.anomaly {
+        AnomalyDetector::ZScore => rolling_scores(values, window, min_spread, z_score),
+        AnomalyDetector::Mad => rolling_scores(values, window, min_spread, mad_score),
+        AnomalyDetector::None => return Vec::new()
+    };
+
+    let mut anomalies: Vec<Anomaly> = Vec::new();
+    let mut previous_flagged = false;
+    for (idx, score) in scores.iter().enumerate() {
+        let flagged = score.abs() > options.anomaly_threshold;
+        if flagged {
+            let sample = idx + window;
+            match anomalies.last_mut() {
+                Some(anomaly) if previous_flagged => {
+                    anomaly.end = seconds[sample];
+                    if score.abs() > anomaly.score.abs() {
+                        anomaly.score = *score;
+                        anomaly.peak_value = values[sample];
+                    }
+                }
+                _ => anomalies.push(Anomaly {
+                    start: seconds[sample],
+                    end: seconds[sample],
+                    peak_value: values[sample],
+                    score: *score
+                })
+            }
+        }
+        previous_flagged = flagged;
+    }
+
+    anomalies
+}
+
+// Scores every sample after the first window against the window of samples before it
+fn rolling_scores(values: &[f64], window: usize, min_spread: f64, score: fn(&[f64], f64, f64) -> f64) -> Vec<f64> {
+    (window..values.len())
+        .map(|idx| score(&values[idx - window..idx], values[idx], min_spread))
+        .collect()
+}
+
+fn z_score(baseline: &[f64], value: f64, min_spread: f64) -> f64 {
+    let amount = baseline.len() as f64;
+    let mean = baseline.iter().sum::<f64>() / amount;
+    let variance = baseline.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (amount - 1.0);
+
+    scale_deviation(value - mean, variance.sqrt(), min_spread, mean)
+}
+
+// Robust z-score based on the median absolute deviation
+fn mad_score(baseline: &[f64], value: f64, min_spread: f64) -> f64 {
+    let center = median(&mut baseline.to_vec());
+    let mut deviations: Vec<f64> = baseline.iter().map(|v| (v - center).abs()).collect();
+    let mad = median(&mut deviations);
+
+    // 0.6745 makes the score comparable with a z-score for normally distributed data
+    scale_deviation(0.6745 * (value - center), mad, min_spread, center)
+}
+
+// The deviation in spreads of the window, which are never below the floor of the metric or a
+// share of the window level
+fn scale_deviation(deviation: f64, spread: f64, min_spread: f64, level: f64) -> f64 {
+    let spread = spread.max(min_spread).max(MIN_SPREAD_SHARE_OF_MEAN * level.abs());
+    if spread > 0.0 {
+        deviation / spread
+    } else {
+        // Only a metric that never changes has no spread at all
+        0.0
+    }
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let middle = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[middle - 1] + values[middle]) / 2.0
+    } else {
+        values[middle]
+    }
+}
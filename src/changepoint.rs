@@ -0,0 +1,157 @@
+use serde::Serialize;
+
+use crate::metric_options::{ChangePointDetector, MetricOptions};
+
+// Stretch of samples with a stable level, in seconds since the first timestamp of the file
+#[derive(Serialize, Debug, Clone)]
+pub struct Segment {
+    pub start: f64,
+    pub end: f64,
+    pub mean: f64
+}
+
+pub fn detect_segments(seconds: &[f64], values: &[f64], options: &MetricOptions) -> Vec<Segment> {
+    let amount = seconds.len().min(values.len());
+    if amount == 0 {
+        return Vec::new();
+    }
+
+    let boundaries = match options.changepoint {
+        ChangePointDetector::Pelt => pelt(&values[..amount], options.changepoint_penalty, options.changepoint_min_segment.max(2)),
+        ChangePointDetector::None => Vec::new()
+    };
+
+    let mut segments = Vec::new();
+    let mut segment_start = 0;
+    for segment_end in boundaries.into_iter().chain(std::iter::once(amount)) {
+        let segment_values = &values[segment_start..segment_end];
+        segments.push(Segment {
+            start: seconds[segment_start],
+            end: seconds[segment_end - 1],
+            mean: segment_values.iter().sum::<f64>() / segment_values.len() as f64
+        });
+        segment_start = segment_end;
+    }
+
+    segments
+}
+
+// Pruned Exact Linear Time search for changes in the mean, with a normal cost.
+// Returns the index of the first sample of every segment but the first.
+fn pelt(values: &[f64], penalty: f64, min_segment: usize) -> Vec<usize> {
+    let amount = values.len();
+    if amount < 2 * min_segment {
+        return Vec::new();
+    }
+
+    let variance = noise_variance(values);
+    if variance <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut sums = vec![0.0; amount + 1];
+    let mut squared_sums = vec![0.0; amount + 1];
+    for (idx, value) in values.iter().enumerate() {
+        sums[idx + 1] = sums[idx] + value;
+        squared_sums[idx + 1] = squared_sums[idx] + value * value;
+    }
+    let cost = |start: usize, end: usize| {
+        let sum = sums[end] - sums[start];
+        (squared_sums[end] - squared_sums[start] - sum * sum / (end - start) as f64) / variance
+    };
+
+    let beta = penalty * (amount as f64).ln();
+    let mut best_cost = vec![f64::INFINITY; amount + 1];
+    let mut previous_change = vec![0; amount + 1];
+    best_cost[0] = -beta;
+
+    let mut candidates: Vec<usize> = Vec::new();
+    for end in min_segment..=amount {
+        let new_candidate = end - min_segment;
+        if new_candidate == 0 || new_candidate >= min_segment {
+            candidates.push(new_candidate);
+        }
+
+        for start in &candidates {
+            let candidate_cost = best_cost[*start] + cost(*start, end) + beta;
+            if candidate_cost < best_cost[end] {
+                best_cost[end] = candidate_cost;
+                previous_change[end] = *start;
+            }
+        }
+
+        let end_cost = best_cost[end];
+        candidates.retain(|start| best_cost[*start] + cost(*start, end) <= end_cost);
+    }
+
+    let mut boundaries = Vec::new();
+    let mut change = previous_change[amount];
+    while change > 0 {
+        boundaries.push(change);
+        change = previous_change[change];
+    }
+    boundaries.reverse();
+
+    boundaries
+}
+
+// Noise estimated from the differences between consecutive samples, so that the level shifts
+// themselves barely inflate it
+fn noise_variance(values: &[f64]) -> f64 {
+    let mut differences: Vec<f64> = values.windows(2).map(|pair| (pair[1] - pair[0]).abs()).collect();
+    differences.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mad = differences[differences.len() / 2];
+    if mad > 0.0 {
+        let sigma = mad / (0.6745 * std::f64::consts::SQRT_2);
+        return sigma * sigma;
+    }
+
+    // Mostly flat series, fall back to the mean squared difference
+    differences.iter().map(|d| d * d).sum::<f64>() / differences.len() as f64 / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deterministic noise between -1 and 1
+    fn noise(amount: usize) -> Vec<f64> {
+        let mut state: u32 = 2021;
+        (0..amount).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as f64 / 32768.0 - 1.0
+        }).collect()
+    }
+
+    #[test]
+    fn finds_a_step() {
+        let values: Vec<f64> = noise(300).iter().enumerate().map(|(idx, noise)| if idx < 100 { 10.0 } else { 20.0 } + noise).collect();
+        assert_eq!(pelt(&values, 10.0, 30), vec![100]);
+    }
+
+    #[test]
+    fn a_flat_series_has_one_segment() {
+        let values: Vec<f64> = noise(300).iter().map(|noise| 10.0 + noise).collect();
+        assert!(pelt(&values, 10.0, 30).is_empty());
+        assert!(pelt(&[5.0; 300], 10.0, 30).is_empty());
+    }
+
+    #[test]
+    fn segments_shorter_than_the_minimum_are_not_split() {
+        let values: Vec<f64> = (0..50).map(|idx| if idx < 25 { 10.0 } else { 20.0 }).collect();
+        assert!(pelt(&values, 10.0, 30).is_empty());
+    }
+
+    #[test]
+    fn segments_have_the_times_and_mean_of_their_samples() {
+        let seconds: Vec<f64> = (0..300).map(|idx| idx as f64 * 2.0).collect();
+        let values: Vec<f64> = noise(300).iter().enumerate().map(|(idx, noise)| if idx < 200 { 50.0 } else { 10.0 } + noise * 0.1).collect();
+        let segments = detect_segments(&seconds, &values, &MetricOptions::default());
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!((segments[0].start, segments[0].end), (0.0, 398.0));
+        assert_eq!((segments[1].start, segments[1].end), (400.0, 598.0));
+        assert!((segments[0].mean - 50.0).abs() < 0.05);
+        assert!((segments[1].mean - 10.0).abs() < 0.05);
+    }
+}
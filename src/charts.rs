@@ -0,0 +1,183 @@
+use anyhow::{bail, Context, Result};
+use log::{debug, warn};
+use serde::Serialize;
+use std::io::{self, BufRead};
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::data_loader::LoadedData;
+use crate::parameters::Parameters;
+use crate::FileName;
+
+// Charts that combine several metrics, written in the charts file as:
+//     chart: Memory and CPU
+//     metrics: Private Bytes
+//     secondary_metrics: % Processor Time
+// Every metric that contains one of the texts is part of the chart, '*' matches any text.
+#[derive(Serialize, Debug)]
+pub struct Chart {
+    pub title: String,
+    pub image: String,
+    // Image filename, without the extension
+    #[serde(skip)]
+    pub image_name: String,
+    pub metrics: Vec<ChartMetric>,
+    // Drawn against the right Y axis
+    pub secondary_metrics: Vec<ChartMetric>,
+    #[serde(skip)]
+    pub layout: ChartLayout,
+    // Subplots per row of grid charts
+    #[serde(skip)]
+    pub columns: usize
+}
+
+#[derive(Serialize, Debug)]
+pub struct ChartMetric {
+    pub name: String,
+    // Where the metric is in the report
+    pub key: String
+}
+
+// Overlay draws every metric on the same plot, grid draws one subplot per metric
+#[derive(Debug, Clone)]
+pub enum ChartLayout {
+    Overlay,
+    Grid
+}
+
+struct ChartDefinition {
+    title: String,
+    metrics: Vec<String>,
+    secondary_metrics: Vec<String>,
+    layout: ChartLayout,
+    columns: usize
+}
+
+impl ChartDefinition {
+
+    fn new(title: &str) -> ChartDefinition {
+        ChartDefinition {
+            title: title.to_string(),
+            metrics: Vec::new(),
+            secondary_metrics: Vec::new(),
+            layout: ChartLayout::Overlay,
+            columns: 2
+        }
+    }
+
+    fn apply(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "metrics" => self.metrics.push(value.to_string()),
+            "secondary_metrics" => self.secondary_metrics.push(value.to_string()),
+            "layout" => self.layout = match value {
+                "overlay" => ChartLayout::Overlay,
+                "grid" => ChartLayout::Grid,
+                _ => bail!("Wrong layout {}. Options are <overlay> or <grid>", value)
+            },
+            "columns" => {
+                self.columns = value.parse::<usize>()
+                    .with_context(|| format!("Invalid columns {}", value))?;
+                if self.columns == 0 {
+                    bail!("columns should be at least 1");
+                }
+            }
+            _ => bail!("Unknown chart setting {}", key)
+        }
+
+        Ok(())
+    }
+}
+
+pub fn load_charts(loaded_data: &LoadedData, param: &Parameters) -> Result<Vec<Chart>> {
+    let definitions = read_definitions(&param.charts_file)?;
+    let extension = param.image_format.report_extension();
+
+    let mut distinct_metrics: Vec<String> = loaded_data.get_distinct_metrics().into_iter().collect();
+    distinct_metrics.sort();
+    let matching = |patterns: &[String]| -> Vec<ChartMetric> {
+        distinct_metrics.iter()
+            .filter(|metric| patterns.iter().any(|pattern| matches_pattern(metric, pattern)))
+            .map(|metric| ChartMetric { name: metric.clone(), key: metric.get_file_name("") })
+            .collect()
+    };
+
+    let mut charts = Vec::new();
+    for definition in definitions {
+        let metrics = matching(&definition.metrics);
+        let secondary_metrics: Vec<ChartMetric> = matching(&definition.secondary_metrics).into_iter()
+            .filter(|secondary| !metrics.iter().any(|metric| metric.name == secondary.name))
+            .collect();
+        if metrics.is_empty() && secondary_metrics.is_empty() {
+            warn!("No metric matches the chart {:?}, skipped", definition.title);
+            continue;
+        }
+
+        let image_name = format!("chart_{}", definition.title).get_file_name("");
+        charts.push(Chart {
+            title: definition.title,
+            image: format!("{}{}", image_name, extension),
+            image_name,
+            metrics,
+            secondary_metrics,
+            layout: definition.layout,
+            columns: definition.columns
+        });
+    }
+
+    debug!("Charts: {:?}", charts);
+    Ok(charts)
+}
+
+fn read_definitions(charts_location: &PathBuf) -> Result<Vec<ChartDefinition>> {
+    let mut definitions: Vec<ChartDefinition> = Vec::new();
+    let file = File::open(charts_location)
+        .with_context(|| format!("Could not open file {:?}", charts_location))?;
+    let reader = io::BufReader::new(file).lines();
+
+    for line in reader.map_while(Result::ok) {
+        if line.trim().is_empty() || line.trim_start().starts_with("//") {
+            continue;
+        }
+
+        // Split only once, so that titles and metrics can contain ':'
+        let config: Vec<&str> = line.splitn(2, ':')
+            .map(|s| s.trim())
+            .collect();
+        if config.len() != 2 {
+            bail!("Chart line {:?} should be written as <key: value>", line);
+        }
+
+        if config[0] == "chart" {
+            if definitions.iter().any(|definition| definition.title == config[1]) {
+                bail!("There is more than one chart called {:?}", config[1]);
+            }
+            definitions.push(ChartDefinition::new(config[1]));
+            continue;
+        }
+
+        let definition = match definitions.last_mut() {
+            Some(definition) => definition,
+            None => bail!("Chart line {:?} comes before the first <chart: title> line", line)
+        };
+        definition.apply(config[0], config[1])
+            .with_context(|| format!("Problem in the chart {:?}", definition.title))?;
+        if let (ChartLayout::Grid, false) = (&definition.layout, definition.secondary_metrics.is_empty()) {
+            bail!("The grid chart {:?} can not have secondary_metrics", definition.title);
+        }
+    }
+
+    Ok(definitions)
+}
+
+// Whether the metric contains the pattern, with '*' matching any text
+fn matches_pattern(metric: &str, pattern: &str) -> bool {
+    let mut rest = metric;
+    for part in pattern.split('*').filter(|part| !part.is_empty()) {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false
+        }
+    }
+
+    true
+}
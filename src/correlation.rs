@@ -0,0 +1,149 @@
+use serde::Serialize;
+
+use crate::data_loader::FileData;
+use crate::parameters::CorrelationMethod;
+use crate::FileName;
+
+static TOP_CORRELATIONS_AMOUNT: usize = 5;
+
+// Correlations between every pair of metrics of a file. The metrics of a file share the same
+// timestamps (or the same grid, when resampled), so samples are compared one to one.
+#[derive(Serialize, Debug)]
+pub struct FileCorrelations {
+    pub file_name: String,
+    pub image: String,
+    #[serde(skip)]
+    pub metrics: Vec<String>,
+    #[serde(skip)]
+    pub pearson: Vec<Vec<f64>>,
+    #[serde(skip)]
+    pub spearman: Vec<Vec<f64>>,
+    pub top: Vec<TopCorrelations>
+}
+
+#[derive(Serialize, Debug)]
+pub struct TopCorrelations {
+    pub metric: String,
+    pub correlated: Vec<Correlation>
+}
+
+#[derive(Serialize, Debug)]
+pub struct Correlation {
+    pub metric: String,
+    pub pearson: f64,
+    pub spearman: f64
+}
+
+impl FileCorrelations {
+
+    pub fn get_matrix(&self, method: &CorrelationMethod) -> &Vec<Vec<f64>> {
+        match method {
+            CorrelationMethod::Pearson => &self.pearson,
+            CorrelationMethod::Spearman => &self.spearman
+        }
+    }
+}
+
+pub fn calculate_correlations(file_data: &FileData, method: &CorrelationMethod, image_extension: &str) -> Option<FileCorrelations> {
+    let mut metrics: Vec<String> = file_data.metrics.keys().cloned().collect();
+    if metrics.len() < 2 {
+        return None;
+    }
+    metrics.sort();
+
+    let series: Vec<&Vec<f64>> = metrics.iter().map(|metric| &file_data.metrics[metric].data).collect();
+    let ranks: Vec<Vec<f64>> = series.iter().map(|values| rank(values)).collect();
+
+    let amount = metrics.len();
+    let mut pearson = vec![vec![0.0; amount]; amount];
+    let mut spearman = vec![vec![0.0; amount]; amount];
+    for i in 0..amount {
+        pearson[i][i] = 1.0;
+        spearman[i][i] = 1.0;
+        for j in (i + 1)..amount {
+            pearson[i][j] = pearson_coefficient(series[i], series[j]);
+            pearson[j][i] = pearson[i][j];
+            spearman[i][j] = pearson_coefficient(&ranks[i], &ranks[j]);
+            spearman[j][i] = spearman[i][j];
+        }
+    }
+
+    let mut correlations = FileCorrelations {
+        file_name: file_data.file_name.clone(),
+        image: format!("correlation_{}", file_data.file_name).get_file_name(image_extension),
+        metrics,
+        pearson,
+        spearman,
+        top: Vec::new()
+    };
+    correlations.top = top_correlations(&correlations, method);
+
+    Some(correlations)
+}
+
+// For each metric, the other metrics that move the most with it, by absolute coefficient
+fn top_correlations(correlations: &FileCorrelations, method: &CorrelationMethod) -> Vec<TopCorrelations> {
+    let matrix = correlations.get_matrix(method);
+
+    correlations.metrics.iter().enumerate().map(|(i, metric)| {
+        let mut others: Vec<usize> = (0..correlations.metrics.len()).filter(|j| *j != i).collect();
+        others.sort_by(|a, b| matrix[i][*b].abs().partial_cmp(&matrix[i][*a].abs()).unwrap_or(std::cmp::Ordering::Equal));
+
+        TopCorrelations {
+            metric: metric.clone(),
+            correlated: others.into_iter().take(TOP_CORRELATIONS_AMOUNT).map(|j| Correlation {
+                metric: correlations.metrics[j].clone(),
+                pearson: correlations.pearson[i][j],
+                spearman: correlations.spearman[i][j]
+            }).collect()
+        }
+    }).collect()
+}
+
+// Metrics without variation do not correlate with anything, so they get 0 instead of NaN
+fn pearson_coefficient(x: &[f64], y: &[f64]) -> f64 {
+    let amount = x.len().min(y.len());
+    if amount < 2 {
+        return 0.0;
+    }
+
+    let mean_x = x[..amount].iter().sum::<f64>() / amount as f64;
+    let mean_y = y[..amount].iter().sum::<f64>() / amount as f64;
+    let mut covariance = 0.0;
+    let mut variance_x = 0.0;
+    let mut variance_y = 0.0;
+    for (a, b) in x.iter().zip(y) {
+        covariance += (a - mean_x) * (b - mean_y);
+        variance_x += (a - mean_x) * (a - mean_x);
+        variance_y += (b - mean_y) * (b - mean_y);
+    }
+
+    if variance_x == 0.0 || variance_y == 0.0 {
+        return 0.0;
+    }
+
+    covariance / (variance_x * variance_y).sqrt()
+}
+
+// Ranks starting at 1, ties get the average of their ranks
+fn rank(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].partial_cmp(&values[*b]).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+
+        let average_rank = (start + end) as f64 / 2.0 + 1.0;
+        for idx in &order[start..=end] {
+            ranks[*idx] = average_rank;
+        }
+        start = end + 1;
+    }
+
+    ranks
+}
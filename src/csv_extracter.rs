@@ -7,6 +7,7 @@ use std::time::Instant;
 use rayon::prelude::*;
 
 use crate::parameters::Parameters;
+use crate::metric_options::split_metric_line;
 
 struct MetricRules {
     metrics: Vec<String>,
@@ -14,7 +15,7 @@ struct MetricRules {
 }
 
 
-pub fn extract_columns_base(file_list: &Vec<PathBuf>, parsed_file_list: &Vec<PathBuf>, param: &Parameters) {
+pub fn extract_columns_base(file_list: &[PathBuf], parsed_file_list: &[PathBuf], param: &Parameters) {
     if !param.skip_parse {
         debug!("Parallel Parsing files: {:?}", &file_list);
         info!("Parsing csv..");
@@ -25,9 +26,8 @@ pub fn extract_columns_base(file_list: &Vec<PathBuf>, parsed_file_list: &Vec<Pat
         file_list.par_iter()
             .zip(parsed_file_list)
             .for_each(|(file_name, altered_file_name)| {
-                match extract_columns(&file_name, &altered_file_name, &rules) {
-                    Err(e) => panic!("{:?}", e),
-                    Ok(_) => (),
+                if let Err(e) = extract_columns(file_name, altered_file_name, &rules) {
+                    panic!("{:?}", e)
                 }
             });
 
@@ -44,19 +44,20 @@ fn extract_metric_rules(wanted_metrics_location: &PathBuf) -> Result<MetricRules
 
     let mut ignore_metric_flag = false;
     for result_line in reader {
-        if let Ok(line) = result_line {
-            if line.is_empty() {
-                continue;
-            }
-            if line == "#$%#$%THIS_IS_THE_SEPARATOR. UP ARE WANTED METRICS, BELOW ARE IGNORED METRICS." {
-                ignore_metric_flag = true;
-            } else {
-                if ignore_metric_flag {
-                    ignore.push(line);
-                } else {
-                    metrics.push(line);
-                }
-            }
+        let line = match result_line {
+            Ok(line) => line,
+            Err(_) => continue
+        };
+        if line.is_empty() {
+            continue;
+        }
+        if line == "#$%#$%THIS_IS_THE_SEPARATOR. UP ARE WANTED METRICS, BELOW ARE IGNORED METRICS." {
+            ignore_metric_flag = true;
+        } else if ignore_metric_flag {
+            ignore.push(line);
+        } else {
+            // Anything after '|' are the metric options
+            metrics.push(split_metric_line(&line).0.to_string());
         }
     }
 
@@ -87,10 +88,8 @@ fn extract_columns(original_csv_name: &PathBuf, parsed_csv_name: &PathBuf, rules
     let mut relevant_idxs: Vec<usize> = vec![0];
 
     for (idx, header) in headers.iter().enumerate() {
-        if rules.metrics.iter().any(|metric| header.contains(metric)) {
-            if !rules.ignore.iter().any(|ignore| header.contains(ignore)) {
-                &relevant_idxs.push(idx);
-            }
+        if rules.metrics.iter().any(|metric| header.contains(metric)) && !rules.ignore.iter().any(|ignore| header.contains(ignore)) {
+            relevant_idxs.push(idx);
         }
     }
     debug!("Relevant idxs are {:?}", relevant_idxs);
@@ -104,7 +103,7 @@ fn extract_columns(original_csv_name: &PathBuf, parsed_csv_name: &PathBuf, rules
         };
 
         for i in &relevant_idxs {
-            &row_to_add.push(current_row[*i].to_string());
+            row_to_add.push(current_row[*i].to_string());
         }
         writer.write_record(row_to_add)
             .with_context(|| format!("There was an issue writing to file {:?}", &parsed_csv_file))?;
@@ -54,7 +54,11 @@ impl LoadedData {
         &self.file_data
     }
 
-    pub fn load_file_data(cvs_file_list: &Vec<PathBuf>, param: &Parameters) -> Result<LoadedData> {
+    pub fn get_all_data_mut(&mut self) -> &mut Vec<FileData> {
+        &mut self.file_data
+    }
+
+    pub fn load_file_data(cvs_file_list: &[PathBuf], param: &Parameters) -> Result<LoadedData> {
         let start = Instant::now();
         info!("Loading csv..");
     
@@ -101,11 +105,11 @@ impl LoadedData {
                 }
             }
     
-            &columns.remove(&index_column_map[&0]);
+            columns.remove(&index_column_map[&0]);
     
             let file_data = FileData {
                 metrics: columns,
-                timestamps: timestamps,
+                timestamps,
                 file_name: parsed_file.file_name().unwrap().to_string_lossy().to_string(),
             };
     
@@ -121,7 +125,19 @@ impl LoadedData {
 impl FileData {
     
     pub fn contains_metric(&self, name: &str) -> bool {
-        return self.metrics.contains_key(name)
+        self.metrics.contains_key(name)
+    }
+
+    // Seconds since the first timestamp, for each sample
+    pub fn get_elapsed_seconds(&self) -> Vec<f64> {
+        let first_timestamp = match self.timestamps.data.first() {
+            Some(time) => *time,
+            None => return Vec::new()
+        };
+
+        self.timestamps.data.iter()
+            .map(|time| time.signed_duration_since(first_timestamp).num_microseconds().unwrap_or(0) as f64 / 1_000_000.0)
+            .collect()
     }
 
 }
@@ -0,0 +1,156 @@
+use crate::parameters::{Parameters, Downsampling};
+
+// Reduces a line to about one point per pixel of the given width. Lines that already fit,
+// and plots with a width per point, are kept as they are.
+pub fn downsample(points: Vec<(f64, f64)>, pixels: usize, param: &Parameters) -> Vec<(f64, f64)> {
+    if param.width_per_point.is_some() {
+        return points;
+    }
+
+    match param.downsampling {
+        Downsampling::Lttb => largest_triangle_three_buckets(points, pixels),
+        Downsampling::MinMax => min_max(points, pixels),
+        Downsampling::None => points
+    }
+}
+
+// Keeps the first and last points, and from every bucket in between the point that forms the
+// largest triangle with the point kept before it and the average of the next bucket
+fn largest_triangle_three_buckets(points: Vec<(f64, f64)>, threshold: usize) -> Vec<(f64, f64)> {
+    let amount = points.len();
+    if threshold < 3 || amount <= threshold {
+        return points;
+    }
+
+    let bucket_size = (amount - 2) as f64 / (threshold - 2) as f64;
+    let mut sampled = Vec::with_capacity(threshold);
+    sampled.push(points[0]);
+
+    let mut previous = 0;
+    for bucket in 0..(threshold - 2) {
+        let next_start = ((bucket + 1) as f64 * bucket_size) as usize + 1;
+        let next_end = (((bucket + 2) as f64 * bucket_size) as usize + 1).min(amount);
+        let next = &points[next_start..next_end];
+        let average_x = next.iter().map(|(x, _)| x).sum::<f64>() / next.len() as f64;
+        let average_y = next.iter().map(|(_, y)| y).sum::<f64>() / next.len() as f64;
+
+        let start = (bucket as f64 * bucket_size) as usize + 1;
+        let end = next_start;
+        let (previous_x, previous_y) = points[previous];
+        let mut largest_area = -1.0;
+        let mut chosen = start;
+        for (idx, (x, y)) in points.iter().enumerate().take(end).skip(start) {
+            let area = ((previous_x - average_x) * (y - previous_y) - (previous_x - x) * (average_y - previous_y)).abs();
+            if area > largest_area {
+                largest_area = area;
+                chosen = idx;
+            }
+        }
+
+        sampled.push(points[chosen]);
+        previous = chosen;
+    }
+
+    sampled.push(points[amount - 1]);
+    sampled
+}
+
+// Splits the X range into one bucket per pixel and keeps the lowest and highest point of each,
+// in their original order
+fn min_max(points: Vec<(f64, f64)>, pixels: usize) -> Vec<(f64, f64)> {
+    let amount = points.len();
+    if pixels == 0 || amount <= pixels * 2 {
+        return points;
+    }
+
+    let first_x = points[0].0;
+    let bucket_width = (points[amount - 1].0 - first_x) / pixels as f64;
+    if bucket_width <= 0.0 {
+        return points;
+    }
+
+    let mut sampled = Vec::with_capacity(pixels * 2 + 2);
+    let mut bucket_start = 0;
+    while bucket_start < amount {
+        let bucket = (((points[bucket_start].0 - first_x) / bucket_width) as usize).min(pixels - 1);
+        let mut bucket_end = bucket_start + 1;
+        while bucket_end < amount && (((points[bucket_end].0 - first_x) / bucket_width) as usize).min(pixels - 1) == bucket {
+            bucket_end += 1;
+        }
+
+        let mut lowest = bucket_start;
+        let mut highest = bucket_start;
+        for idx in bucket_start..bucket_end {
+            if points[idx].1 < points[lowest].1 {
+                lowest = idx;
+            }
+            if points[idx].1 > points[highest].1 {
+                highest = idx;
+            }
+        }
+
+        sampled.push(points[lowest.min(highest)]);
+        if lowest != highest {
+            sampled.push(points[lowest.max(highest)]);
+        }
+        bucket_start = bucket_end;
+    }
+
+    // The line still reaches the last sample
+    if sampled.last() != points.last() {
+        sampled.push(points[amount - 1]);
+    }
+
+    sampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wave(amount: usize) -> Vec<(f64, f64)> {
+        (0..amount).map(|idx| (idx as f64, (idx as f64 / 10.0).sin())).collect()
+    }
+
+    #[test]
+    fn lttb_keeps_the_ends_and_the_threshold() {
+        let points = wave(1000);
+        let sampled = largest_triangle_three_buckets(points.clone(), 100);
+
+        assert_eq!(sampled.len(), 100);
+        assert_eq!(sampled.first(), points.first());
+        assert_eq!(sampled.last(), points.last());
+        assert!(sampled.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn lttb_keeps_a_spike() {
+        let mut points: Vec<(f64, f64)> = (0..1000).map(|idx| (idx as f64, 1.0)).collect();
+        points[503].1 = 100.0;
+        assert!(largest_triangle_three_buckets(points, 50).contains(&(503.0, 100.0)));
+    }
+
+    #[test]
+    fn min_max_keeps_a_single_sample_spike() {
+        let mut points: Vec<(f64, f64)> = (0..10_000).map(|idx| (idx as f64, 1.0)).collect();
+        points[5003].1 = 100.0;
+        points[7001].1 = -100.0;
+        let sampled = min_max(points.clone(), 100);
+
+        assert!(sampled.len() <= 2 * 100 + 1);
+        assert!(sampled.contains(&(5003.0, 100.0)));
+        assert!(sampled.contains(&(7001.0, -100.0)));
+        assert_eq!(sampled.first(), points.first());
+        assert_eq!(sampled.last(), points.last());
+        assert!(sampled.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn lines_that_fit_are_kept() {
+        let points = wave(100);
+        assert_eq!(largest_triangle_three_buckets(points.clone(), 100), points);
+        assert_eq!(largest_triangle_three_buckets(points.clone(), 2), points);
+        assert_eq!(min_max(points.clone(), 50), points);
+        assert_eq!(min_max(points.clone(), 0), points);
+    }
+}
@@ -0,0 +1,142 @@
+// Number and unit formatting shared by the report and the plots
+
+static SI_PREFIXES: [(f64, &str); 7] = [(1e12, "T"), (1e9, "G"), (1e6, "M"), (1e3, "k"), (1.0, ""), (1e-3, "m"), (1e-6, "µ")];
+// Text of the counter name and the unit of its values, the first match wins. Counters that end
+// with /sec are rates of the unit of the rest of their name.
+static UNIT_RULES: [(&str, &str); 8] = [
+    ("%", "%"),
+    ("MBytes", "MB"),
+    ("KBytes", "KB"),
+    ("Bytes", "B"),
+    // Avg. Disk sec/Read
+    ("sec/", "s"),
+    // ASP.NET reports these in milliseconds
+    ("Request Execution Time", "ms"),
+    ("Request Wait Time", "ms"),
+    ("Up Time", "s")
+];
+
+static IEC_PREFIXES: [(f64, &str); 5] = [(1_099_511_627_776.0, "Ti"), (1_073_741_824.0, "Gi"), (1_048_576.0, "Mi"), (1024.0, "Ki"), (1.0, "")];
+
+// Unit of a metric from the counter name, the part after the last backslash. Unknown counters have no unit.
+pub fn infer_unit(metric: &str) -> &'static str {
+    let counter = metric.rsplit('\\').next().unwrap_or(metric);
+    if let Some(quantity) = counter.strip_suffix("/sec") {
+        // Bytes Received/sec, Pages/sec
+        return match counter_unit(quantity) {
+            "B" => "B/s",
+            "KB" => "KB/s",
+            "MB" => "MB/s",
+            _ => "/s"
+        };
+    }
+
+    counter_unit(counter)
+}
+
+fn counter_unit(counter: &str) -> &'static str {
+    UNIT_RULES.iter()
+        .find(|(text, _)| counter.contains(text))
+        .map(|(_, unit)| *unit)
+        .unwrap_or("")
+}
+
+// Fixed amount of decimals, with thousands separators
+pub fn format_number(value: f64, decimals: usize) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+
+    let text = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = match text.find('.') {
+        Some(idx) => (&text[..idx], &text[idx..]),
+        None => (text.as_str(), "")
+    };
+
+    let mut grouped = String::new();
+    for (idx, digit) in integer.chars().enumerate() {
+        if idx > 0 && (integer.len() - idx) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if value < 0.0 && text.chars().any(|digit| digit.is_ascii_digit() && digit != '0') { "-" } else { "" };
+    format!("{}{}{}", sign, grouped, fraction)
+}
+
+// The value with a prefix that keeps it short, such as 1.5 GiB, 20 ms or 12k. Byte units use
+// the binary (IEC) prefixes, percentages are kept as they are. Values in KB, MB, GB or ms are
+// converted to bytes or seconds first, so that 4096 MB reads as 4 GiB.
+pub fn format_unit(value: f64, unit: &str) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+
+    let (scale, unit) = base_unit(unit);
+    let value = value * scale;
+    let prefixes: &[(f64, &str)] = match unit {
+        "%" => &[(1.0, "")],
+        // Milli and micro read as units of their own without one
+        "" => &SI_PREFIXES[..5],
+        // Thousands of seconds read better as seconds
+        "s" => &SI_PREFIXES[4..],
+        _ if unit.starts_with('B') => &IEC_PREFIXES,
+        _ => &SI_PREFIXES
+    };
+    let (factor, prefix) = prefixes.iter()
+        .find(|(factor, _)| value.abs() >= *factor)
+        .unwrap_or_else(|| if value == 0.0 { &(1.0, "") } else { prefixes.last().unwrap() });
+
+    let number = format_significant(value / factor);
+    match unit {
+        "" | "/s" => format!("{}{}{}", number, prefix, unit),
+        "%" => format!("{}%", number),
+        _ => format!("{} {}{}", number, prefix, unit)
+    }
+}
+
+// Largest two units of a duration, such as 45s, 12m 05s or 3h 02m
+pub fn format_duration(seconds: f64) -> String {
+    if !seconds.is_finite() || seconds < 0.0 {
+        return String::new();
+    }
+
+    let seconds = seconds.round() as u64;
+    match seconds {
+        _ if seconds >= 86_400 => format!("{}d {:02}h", seconds / 86_400, seconds % 86_400 / 3600),
+        _ if seconds >= 3600 => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+        _ if seconds >= 60 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}s", seconds)
+    }
+}
+
+// Factor to the unit without a prefix, and that unit
+fn base_unit(unit: &str) -> (f64, &str) {
+    match unit {
+        "KB" => (1024.0, "B"),
+        "MB" => (1_048_576.0, "B"),
+        "GB" => (1_073_741_824.0, "B"),
+        "KB/s" => (1024.0, "B/s"),
+        "MB/s" => (1_048_576.0, "B/s"),
+        "ms" => (1e-3, "s"),
+        _ => (1.0, unit)
+    }
+}
+
+// About three significant digits, without trailing zeros
+fn format_significant(value: f64) -> String {
+    let decimals = match value.abs() {
+        magnitude if magnitude >= 100.0 => 0,
+        magnitude if magnitude >= 10.0 => 1,
+        magnitude if magnitude >= 1.0 || magnitude == 0.0 => 2,
+        magnitude => (2 - magnitude.log10().floor() as i32).min(9) as usize
+    };
+
+    let text = format!("{:.*}", decimals, value);
+    let text = if text.contains('.') { text.trim_end_matches('0').trim_end_matches('.') } else { text.as_str() };
+    match text {
+        "-0" => "0".to_string(),
+        _ => text.to_string()
+    }
+}
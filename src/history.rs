@@ -0,0 +1,280 @@
+use std::fs::{self, File};
+use std::time::Instant;
+use std::path::{Component, Path, PathBuf};
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use anyhow::{Context, Result};
+use handlebars::{Handlebars, to_json};
+use log::{debug, info, warn};
+use rusqlite::{Connection, params};
+use serde::Serialize;
+use serde_json::value::Map;
+
+use crate::data_loader::LoadedData;
+use crate::html_renderer::{include_asset, register_helpers, register_image_helper, register_partials};
+use crate::parameters::{Parameters, verify_file_exists};
+use crate::plotter::generate_history_plots;
+use crate::statistics::Statistics;
+use crate::stats_exporter::RunMetadata;
+use crate::FileName;
+
+static SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS run (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        generated TEXT NOT NULL,
+        napal_version TEXT NOT NULL,
+        target_directory TEXT NOT NULL,
+        inputs TEXT NOT NULL,
+        start TEXT,
+        end TEXT
+    );
+    CREATE TABLE IF NOT EXISTS tag (
+        run_id INTEGER NOT NULL REFERENCES run(id),
+        key TEXT NOT NULL,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS statistic (
+        run_id INTEGER NOT NULL REFERENCES run(id),
+        metric TEXT NOT NULL,
+        file_name TEXT NOT NULL,
+        unit TEXT NOT NULL,
+        average REAL,
+        median REAL,
+        p1 REAL,
+        p25 REAL,
+        p75 REAL,
+        p90 REAL,
+        p99 REAL,
+        growth_per_hour REAL,
+        anomalies INTEGER NOT NULL,
+        change_points INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tag_run ON tag(run_id);
+    CREATE INDEX IF NOT EXISTS statistic_run ON statistic(run_id);
+";
+
+// A recorded analysis
+#[derive(Serialize)]
+pub struct HistoryRun {
+    pub id: i64,
+    pub generated: String,
+    pub inputs: Vec<String>,
+    pub tags: Vec<(String, String)>,
+    // Report of the run, relative to the history report
+    pub report: String
+}
+
+// The recorded statistics of a metric, in run order and by file name within a run
+#[derive(Serialize)]
+pub struct HistoryMetric {
+    pub name: String,
+    pub unit: String,
+    pub image: String,
+    pub points: Vec<HistoryPoint>
+}
+
+// Statistics of a metric in a file of a run
+#[derive(Serialize)]
+pub struct HistoryPoint {
+    pub run_id: i64,
+    pub file_name: String,
+    pub average: f64,
+    pub p90th_percentile: f64,
+    pub p99th_percentile: f64
+}
+
+// Records the run, its tags and the statistics of every metric and file in the history database
+pub fn record_run(loaded_data: &LoadedData, statistics: &Statistics, input_files: &[PathBuf], param: &Parameters) -> Result<()> {
+    if !param.record_history {
+        return Ok(());
+    }
+    let start = Instant::now();
+    info!("Recording history..");
+
+    let mut connection = open_database(&param.history_file)?;
+    let metadata = RunMetadata::new(loaded_data, input_files, param);
+    let target_directory = fs::canonicalize(&param.target_directory)
+        .with_context(|| format!("Could not find directory {:?}", &param.target_directory))?;
+    let inputs = serde_json::to_string(&metadata.inputs)
+        .with_context(|| "Inputs serialization problems (weird...)")?;
+
+    // Either the whole run is recorded or none of it
+    let transaction = connection.transaction()
+        .with_context(|| format!("Could not write to database {:?}", &param.history_file))?;
+    transaction.execute("INSERT INTO run (generated, napal_version, target_directory, inputs, start, end) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![metadata.generated, metadata.napal_version, target_directory.to_string_lossy(), inputs, metadata.start, metadata.end])
+        .with_context(|| format!("Could not write to database {:?}", &param.history_file))?;
+    let run_id = transaction.last_insert_rowid();
+
+    for (key, value) in &param.tags {
+        transaction.execute("INSERT INTO tag (run_id, key, value) VALUES (?1, ?2, ?3)", params![run_id, key, value])
+            .with_context(|| format!("Could not write to database {:?}", &param.history_file))?;
+    }
+
+    for (_, metric_statistics) in statistics.sorted_stats() {
+        for (file_name, stat) in &metric_statistics.files {
+            transaction.execute("INSERT INTO statistic (run_id, metric, file_name, unit, average, median, p1, p25, p75, p90, p99, growth_per_hour, anomalies, change_points) \
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                params![run_id, metric_statistics.name, file_name, metric_statistics.unit, stat.average, stat.median, stat.p1st_percentile,
+                    stat.p25th_percentile, stat.p75th_percentile, stat.p90th_percentile, stat.p99th_percentile, stat.trend.growth_per_hour,
+                    stat.anomalies.len() as i64, stat.segments.len().saturating_sub(1) as i64])
+                .with_context(|| format!("Could not write to database {:?}", &param.history_file))?;
+        }
+    }
+    transaction.commit()
+        .with_context(|| format!("Could not write to database {:?}", &param.history_file))?;
+
+    debug!("Recorded run {} in {:?}", run_id, &param.history_file);
+    debug!("Sequencial history recording: {:?}", start.elapsed());
+    Ok(())
+}
+
+// The history command: trend charts of the statistics of the latest runs that have every given
+// tag, in history.htm
+pub fn generate_history(param: &Parameters) -> Result<()> {
+    let start = Instant::now();
+    info!("Generating history..");
+
+    if !param.history_file.exists() {
+        warn!("There is no history database at {:?}, analyse some files first", &param.history_file);
+        return Ok(());
+    }
+    let connection = open_database(&param.history_file)?;
+    let runs = load_runs(&connection, param)?;
+    if runs.is_empty() {
+        warn!("No recorded run has the given tags");
+        return Ok(());
+    }
+    let metrics = load_metrics(&connection, &runs, param)?;
+    debug!("History of {} runs and {} metrics", runs.len(), metrics.len());
+
+    generate_history_plots(&runs, &metrics, param)?;
+
+    let template_location = verify_file_exists(&"templates/history.hbs".to_string());
+    let mut handlebars = Handlebars::new();
+    handlebars.register_template_file("history", &template_location)
+        .with_context(|| format!("Could not register handlebars template {:?}", &template_location))?;
+    register_partials(&mut handlebars, &verify_file_exists(&"templates/partials".to_string()))?;
+    register_helpers(&mut handlebars);
+    register_image_helper(&mut handlebars, param);
+
+    let mut data = Map::new();
+    data.insert("run".to_string(), to_json(&runs));
+    data.insert("metric".to_string(), to_json(&metrics));
+    data.insert("single_file".to_string(), to_json(param.single_file));
+    include_asset("report.css", "stylesheet", &mut data, param)?;
+    include_asset("report.js", "report_script", &mut data, param)?;
+
+    let history_path = Path::new(&param.target_directory).join("history.htm");
+    let history_content = handlebars.render("history", &data)
+        .with_context(|| format!("Could not render the template {:?}", &template_location))?;
+    let mut history_file = File::create(&history_path)
+        .with_context(|| format!("Could not create file {:?}", &history_path))?;
+    history_file.write_all(history_content.as_bytes())
+        .with_context(|| format!("Could not write to file {:?}", history_path))?;
+
+    debug!("Sequencial history generation: {:?}", start.elapsed());
+    Ok(())
+}
+
+fn open_database(history_file: &Path) -> Result<Connection> {
+    if let Some(directory) = history_file.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+        fs::create_dir_all(directory)
+            .with_context(|| format!("Could not create directory {:?}", directory))?;
+    }
+    let connection = Connection::open(history_file)
+        .with_context(|| format!("Could not open database {:?}", history_file))?;
+    connection.execute_batch(SCHEMA)
+        .with_context(|| format!("Could not create the tables of database {:?}", history_file))?;
+
+    Ok(connection)
+}
+
+// The latest runs that have every tag of the parameters, oldest first
+fn load_runs(connection: &Connection, param: &Parameters) -> Result<Vec<HistoryRun>> {
+    let history_directory = fs::canonicalize(&param.target_directory)
+        .with_context(|| format!("Could not find directory {:?}", &param.target_directory))?;
+
+    let mut run_statement = connection.prepare("SELECT id, generated, target_directory, inputs FROM run ORDER BY id DESC")
+        .with_context(|| format!("Could not read database {:?}", &param.history_file))?;
+    let mut tag_statement = connection.prepare("SELECT key, value FROM tag WHERE run_id = ?1 ORDER BY rowid")
+        .with_context(|| format!("Could not read database {:?}", &param.history_file))?;
+
+    let rows = run_statement.query_map(params![], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?)))
+        .with_context(|| format!("Could not read database {:?}", &param.history_file))?;
+    let mut runs = Vec::new();
+    for row in rows {
+        let (id, generated, target_directory, inputs) = row.with_context(|| format!("Could not read database {:?}", &param.history_file))?;
+        let tags = tag_statement.query_map(params![id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .and_then(|tags| tags.collect::<Result<Vec<(String, String)>, _>>())
+            .with_context(|| format!("Could not read database {:?}", &param.history_file))?;
+        if !param.tags.iter().all(|tag| tags.contains(tag)) {
+            continue;
+        }
+
+        runs.push(HistoryRun {
+            id,
+            generated,
+            inputs: serde_json::from_str(&inputs).unwrap_or_default(),
+            tags,
+            report: relative_path(&history_directory, &Path::new(&target_directory).join("index.htm"))
+        });
+        if runs.len() == param.history_runs {
+            break;
+        }
+    }
+    runs.reverse();
+
+    Ok(runs)
+}
+
+// Statistics of the runs, grouped by metric and sorted by name
+fn load_metrics(connection: &Connection, runs: &[HistoryRun], param: &Parameters) -> Result<Vec<HistoryMetric>> {
+    let extension = param.image_format.report_extension();
+    let mut statement = connection.prepare("SELECT metric, unit, file_name, average, p90, p99 FROM statistic WHERE run_id = ?1 ORDER BY file_name")
+        .with_context(|| format!("Could not read database {:?}", &param.history_file))?;
+
+    let mut metrics: BTreeMap<String, HistoryMetric> = BTreeMap::new();
+    for run in runs {
+        let rows = statement.query_map(params![run.id], |row| {
+            // NaN statistics are stored as NULL
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, HistoryPoint {
+                run_id: run.id,
+                file_name: row.get(2)?,
+                average: row.get::<_, Option<f64>>(3)?.unwrap_or(f64::NAN),
+                p90th_percentile: row.get::<_, Option<f64>>(4)?.unwrap_or(f64::NAN),
+                p99th_percentile: row.get::<_, Option<f64>>(5)?.unwrap_or(f64::NAN)
+            }))
+        }).with_context(|| format!("Could not read database {:?}", &param.history_file))?;
+
+        for row in rows {
+            let (name, unit, point) = row.with_context(|| format!("Could not read database {:?}", &param.history_file))?;
+            let metric = metrics.entry(name.clone()).or_insert_with(|| HistoryMetric {
+                image: name.get_file_name(&format!("_history{}", extension)),
+                name,
+                unit: unit.clone(),
+                points: Vec::new()
+            });
+            // The latest run decides the unit
+            metric.unit = unit;
+            metric.points.push(point);
+        }
+    }
+
+    Ok(metrics.into_values().collect())
+}
+
+// Path of `to` from the directory `from`, with forward slashes to be used as a link. Paths on
+// different drives are kept absolute.
+fn relative_path(from: &Path, to: &Path) -> String {
+    let from_components: Vec<Component> = from.components().collect();
+    let to_components: Vec<Component> = to.components().collect();
+    let shared = from_components.iter().zip(&to_components).take_while(|(a, b)| a == b).count();
+    if shared == 0 {
+        return to.to_string_lossy().replace('\\', "/");
+    }
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from_components.len() - shared];
+    parts.extend(to_components[shared..].iter().map(|component| component.as_os_str().to_string_lossy().to_string()));
+    parts.join("/")
+}
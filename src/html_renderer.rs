@@ -1,27 +1,56 @@
-use std::fs::File;
+use std::fs::{self, File};
 use std::time::Instant;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::io::prelude::*;
-use handlebars::{Handlebars};
+use handlebars::{Context as HandlebarsContext, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError, handlebars_helper, to_json};
 use anyhow::{Context, Result};
 use log::{debug, info};
 
+use serde_json::value::{Map, Value as Json};
+
+use crate::charts::Chart;
+use crate::data_loader::LoadedData;
+use crate::formatting::{format_duration, format_number, format_unit};
+use crate::metric_options::MetricOptionsList;
 use crate::parameters::{Parameters, verify_file_exists};
+use crate::plotter::interactive_series;
 use crate::statistics::Statistics;
 
 
-pub fn generate_html(statistics: &Statistics, param: &Parameters) -> Result<()> {
+pub fn generate_html(loaded_data: &LoadedData, statistics: &Statistics, metric_options: &MetricOptionsList, charts: &[Chart], param: &Parameters) -> Result<()> {
     let start = Instant::now();
     info!("Generating HTML..");
-    let template_location = verify_file_exists(&"templates/template.hbs".to_string());
 
     let mut handlebars = Handlebars::new();
-    handlebars.register_template_file("table", &template_location)
-        .with_context(|| format!("Could not register handlebars template {:?}", &template_location))?;
+    handlebars.register_template_file("table", &param.template_file)
+        .with_context(|| format!("Could not register handlebars template {:?}", &param.template_file))?;
+
+    // The partials next to the template replace the default ones with the same name
+    register_partials(&mut handlebars, &verify_file_exists(&"templates/partials".to_string()))?;
+    if let Some(template_directory) = param.template_file.parent() {
+        register_partials(&mut handlebars, &template_directory.join("partials"))?;
+    }
+    register_helpers(&mut handlebars);
+    register_image_helper(&mut handlebars, param);
 
     let base_path = Path::new(&param.target_directory);
+    let mut data = statistics.jsonify();
+    data.insert("chart".to_string(), to_json(charts));
+    data.insert("single_file".to_string(), to_json(param.single_file));
+    data.insert("interactive".to_string(), to_json(param.interactive));
+    include_asset("report.css", "stylesheet", &mut data, param)?;
+    include_asset("report.js", "report_script", &mut data, param)?;
+    if param.interactive {
+        include_asset("charts.js", "script", &mut data, param)?;
+        // Kept inside a script element, which would end at the first "</"
+        let series = serde_json::to_string(&interactive_series(loaded_data, metric_options, param))
+            .with_context(|| "Series serialization problems (weird...)")?;
+        data.insert("series".to_string(), to_json(series.replace("</", "<\\/")));
+    }
+
     let index_path = base_path.join("index.htm");
-    let index_content = handlebars.render("table", &statistics.jsonify()).unwrap();
+    let index_content = handlebars.render("table", &data)
+        .with_context(|| format!("Could not render the template {:?}", &param.template_file))?;
     let mut index_file = File::create(&index_path)
         .with_context(|| format!("Could not create file {:?}", &index_path))?;
     index_file.write_all(index_content.as_bytes())
@@ -29,4 +58,111 @@ pub fn generate_html(statistics: &Statistics, param: &Parameters) -> Result<()>
 
     debug!("Sequencial HTML generation: {:?}", start.elapsed());
     Ok(())
-}
\ No newline at end of file
+}
+
+// Every .hbs file of the directory, by file name without the extension
+pub fn register_partials(handlebars: &mut Handlebars, partials_directory: &PathBuf) -> Result<()> {
+    if !partials_directory.is_dir() {
+        return Ok(());
+    }
+
+    let entries = fs::read_dir(partials_directory)
+        .with_context(|| format!("Could not read directory {:?}", partials_directory))?;
+    for entry in entries {
+        let partial_path = entry.with_context(|| format!("Could not read directory {:?}", partials_directory))?.path();
+        if partial_path.extension().and_then(|extension| extension.to_str()) != Some("hbs") {
+            continue;
+        }
+
+        let name = partial_path.file_stem().unwrap().to_string_lossy().to_string();
+        let content = fs::read_to_string(&partial_path)
+            .with_context(|| format!("Could not read file {:?}", &partial_path))?;
+        handlebars.register_partial(&name, content)
+            .with_context(|| format!("Could not register handlebars partial {:?}", &partial_path))?;
+        debug!("Registered partial {} from {:?}", name, partial_path);
+    }
+
+    Ok(())
+}
+
+// Formatting helpers available to every template:
+//     {{number value decimals}}  fixed decimals with thousands separators
+//     {{unit value "B"}}         short value with SI or IEC prefixes, such as 1.5 GiB
+//     {{delta value "B"}}        signed value coloured by whether it increases or decreases
+//     {{duration seconds}}       largest two units of a duration, such as 3h 02m
+pub fn register_helpers(handlebars: &mut Handlebars) {
+    handlebars_helper!(number_helper: |value: f64, decimals: u64| format_number(value, decimals as usize));
+    handlebars.register_helper("number", Box::new(number_helper));
+    handlebars_helper!(duration_helper: |seconds: f64| format_duration(seconds));
+    handlebars.register_helper("duration", Box::new(duration_helper));
+
+    handlebars.register_helper("unit", Box::new(|h: &Helper, _: &Handlebars, _: &HandlebarsContext, _: &mut RenderContext, out: &mut dyn Output| -> HelperResult {
+        let (value, unit) = value_and_unit(h, "unit")?;
+        out.write(&format_unit(value, unit))?;
+        Ok(())
+    }));
+
+    handlebars.register_helper("delta", Box::new(|h: &Helper, _: &Handlebars, _: &HandlebarsContext, _: &mut RenderContext, out: &mut dyn Output| -> HelperResult {
+        let (value, unit) = value_and_unit(h, "delta")?;
+        let (class, sign) = match value {
+            _ if value > 0.0 => ("delta-increase", "+"),
+            _ if value < 0.0 => ("delta-decrease", ""),
+            _ => ("delta-flat", "")
+        };
+        out.write(&format!("<span class=\"delta {}\">{}{}</span>", class, sign, format_unit(value, unit)))?;
+        Ok(())
+    }));
+}
+
+// Images are linked by name, or embedded in single file reports
+pub fn register_image_helper(handlebars: &mut Handlebars, param: &Parameters) {
+    let image_directory = param.target_directory.clone();
+    let single_file = param.single_file;
+    handlebars.register_helper("image", Box::new(move |h: &Helper, _: &Handlebars, _: &HandlebarsContext, _: &mut RenderContext, out: &mut dyn Output| -> HelperResult {
+        let image = h.param(0).and_then(|image| image.value().as_str())
+            .ok_or_else(|| RenderError::new("The image helper needs the name of the image"))?;
+        if single_file {
+            out.write(&embed_image(&image_directory.join(image)).map_err(|e| RenderError::new(format!("{:?}", e)))?)?;
+        } else {
+            out.write(image)?;
+        }
+        Ok(())
+    }));
+}
+
+// The number and the optional unit given to a helper
+fn value_and_unit<'a>(h: &'a Helper, helper_name: &str) -> Result<(f64, &'a str), RenderError> {
+    let value = h.param(0).and_then(|value| value.value().as_f64())
+        .ok_or_else(|| RenderError::new(format!("The {} helper needs a number", helper_name)))?;
+    let unit = h.param(1).and_then(|unit| unit.value().as_str()).unwrap_or("");
+    Ok((value, unit))
+}
+
+// Files of the templates directory used by the report, embedded in single file reports and
+// copied next to index.htm otherwise
+pub fn include_asset(asset: &str, key: &str, data: &mut Map<String, Json>, param: &Parameters) -> Result<()> {
+    let asset_location = verify_file_exists(&format!("templates/{}", asset));
+    if param.single_file {
+        let content = fs::read_to_string(&asset_location)
+            .with_context(|| format!("Could not read file {:?}", &asset_location))?;
+        data.insert(key.to_string(), to_json(content));
+    } else {
+        let base_path = Path::new(&param.target_directory);
+        fs::copy(&asset_location, base_path.join(asset))
+            .with_context(|| format!("Could not copy {:?} to {:?}", &asset_location, base_path))?;
+    }
+
+    Ok(())
+}
+
+// The image as a base64 data URI
+fn embed_image(image_path: &PathBuf) -> Result<String> {
+    let content = fs::read(image_path)
+        .with_context(|| format!("Could not read image {:?}", image_path))?;
+    let media_type = match image_path.extension().and_then(|extension| extension.to_str()) {
+        Some("svg") => "image/svg+xml",
+        _ => "image/png"
+    };
+
+    Ok(format!("data:{};base64,{}", media_type, base64::encode(content)))
+}
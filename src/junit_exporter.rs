@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::time::Instant;
+use std::path::Path;
+use std::io::prelude::*;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use log::{debug, info, warn};
+
+use crate::formatting::format_unit;
+use crate::metric_options::{Limit, LimitBound, LimitStatistic, MetricOptionsList};
+use crate::parameters::Parameters;
+use crate::statistics::{Stat, Statistics};
+
+// A metric in a file, checked against the limits of its options
+struct TestCase<'a> {
+    metric: &'a str,
+    object: &'a str,
+    failures: Vec<String>,
+    details: Vec<String>
+}
+
+// Writes junit.xml, with a test suite per file and a test case per metric that has limits, so
+// that CI servers show the limits that are exceeded as failing tests. Nothing is written when no
+// metric has limits.
+pub fn export_junit(statistics: &Statistics, metric_options: &MetricOptionsList, param: &Parameters) -> Result<()> {
+    let start = Instant::now();
+
+    // File -> Test cases, sorted by file
+    let mut suites: Vec<(&str, Vec<TestCase>)> = Vec::new();
+    for (_, metric_statistics) in statistics.sorted_stats() {
+        let options = metric_options.get(&metric_statistics.name);
+        if options.limits.is_empty() {
+            continue;
+        }
+
+        for (file_name, stat) in &metric_statistics.files {
+            let mut test_case = TestCase { metric: &metric_statistics.name, object: &metric_statistics.object, failures: Vec::new(), details: Vec::new() };
+            for limit in &options.limits {
+                let observed = statistic_value(stat, limit.statistic);
+                if !limit.accepts(observed) {
+                    test_case.failures.push(failure_message(limit, observed, &metric_statistics.unit));
+                    test_case.details.push(format!("{}: observed {}, expected {} {}", limit.statistic.name(), observed,
+                        if limit.bound == LimitBound::Max { "at most" } else { "at least" }, limit.value));
+                }
+            }
+
+            match suites.iter_mut().find(|(suite, _)| suite == file_name) {
+                Some((_, test_cases)) => test_cases.push(test_case),
+                None => suites.push((file_name, vec![test_case]))
+            }
+        }
+    }
+
+    if suites.is_empty() {
+        return Ok(());
+    }
+    info!("Checking limits..");
+    suites.sort_by(|a, b| a.0.cmp(b.0));
+
+    let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+    let tests: usize = suites.iter().map(|(_, test_cases)| test_cases.len()).sum();
+    let failures: usize = suites.iter()
+        .map(|(_, test_cases)| test_cases.iter().filter(|test_case| !test_case.failures.is_empty()).count())
+        .sum();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<testsuites name=\"napal\" tests=\"{}\" failures=\"{}\">\n", tests, failures));
+    for (file_name, test_cases) in &suites {
+        let suite_failures = test_cases.iter().filter(|test_case| !test_case.failures.is_empty()).count();
+        xml.push_str(&format!("  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" timestamp=\"{}\">\n",
+            escape_xml(file_name), test_cases.len(), suite_failures, timestamp));
+        for test_case in test_cases {
+            // CI servers split the class name on dots into package and class
+            let class_name = format!("{}.{}", file_name.replace('.', "_"), test_case.object.replace('.', "_"));
+            xml.push_str(&format!("    <testcase classname=\"{}\" name=\"{}\"", escape_xml(&class_name), escape_xml(test_case.metric)));
+            if test_case.failures.is_empty() {
+                xml.push_str("/>\n");
+                continue;
+            }
+
+            xml.push_str(">\n");
+            xml.push_str(&format!("      <failure message=\"{}\" type=\"limit\">{}</failure>\n",
+                escape_xml(&test_case.failures.join("; ")), escape_xml(&test_case.details.join("\n"))));
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+
+    let junit_path = Path::new(&param.target_directory).join("junit.xml");
+    let mut junit_file = File::create(&junit_path)
+        .with_context(|| format!("Could not create file {:?}", &junit_path))?;
+    junit_file.write_all(xml.as_bytes())
+        .with_context(|| format!("Could not write to file {:?}", junit_path))?;
+
+    if failures > 0 {
+        warn!("{} of {} metrics exceed their limits, see {:?}", failures, tests, junit_path);
+    }
+    debug!("Sequencial limit checks: {:?}", start.elapsed());
+    Ok(())
+}
+
+fn statistic_value(stat: &Stat, statistic: LimitStatistic) -> f64 {
+    match statistic {
+        LimitStatistic::Average => stat.average,
+        LimitStatistic::Median => stat.median,
+        LimitStatistic::P1 => stat.p1st_percentile,
+        LimitStatistic::P25 => stat.p25th_percentile,
+        LimitStatistic::P75 => stat.p75th_percentile,
+        LimitStatistic::P90 => stat.p90th_percentile,
+        LimitStatistic::P99 => stat.p99th_percentile,
+        LimitStatistic::GrowthPerHour => stat.trend.growth_per_hour,
+        LimitStatistic::Anomalies => stat.anomalies.len() as f64,
+        LimitStatistic::ChangePoints => stat.segments.len().saturating_sub(1) as f64
+    }
+}
+
+// Such as: p90 of 81.2% is above the max_p90 of 80%
+fn failure_message(limit: &Limit, observed: f64, unit: &str) -> String {
+    let unit = if limit.statistic.has_unit() { unit } else { "" };
+    format!("{} of {} is {} the {} of {}", limit.statistic.name(), format_unit(observed, unit),
+        if limit.bound == LimitBound::Max { "above" } else { "below" }, limit.option_name(), format_unit(limit.value, unit))
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
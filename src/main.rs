@@ -1,40 +1,82 @@
+mod annotations;
+mod anomaly;
+mod changepoint;
+mod charts;
+mod correlation;
 mod csv_extracter;
 mod data_loader;
+mod downsampler;
+mod formatting;
+mod history;
 mod html_renderer;
+mod junit_exporter;
+mod markdown_renderer;
+mod metric_options;
 mod parameters;
 mod plotter;
+mod resampler;
+mod results_index;
 mod statistics;
+mod stats_exporter;
 
 use std::time::Instant;
 use std::path::PathBuf;
 use anyhow::Result;
 use log::info;
 
+use crate::annotations::Annotations;
+use crate::charts::load_charts;
 use crate::csv_extracter::extract_columns_base;
 use crate::data_loader::LoadedData;
+use crate::history::{generate_history, record_run};
 use crate::html_renderer::generate_html;
-use crate::parameters::Parameters;
+use crate::junit_exporter::export_junit;
+use crate::markdown_renderer::{generate_markdown, print_summary};
+use crate::metric_options::MetricOptionsList;
+use crate::parameters::{Command, Parameters};
 use crate::plotter::generate_plots;
+use crate::resampler::resample_data;
+use crate::results_index::generate_results_index;
 use crate::statistics::Statistics;
+use crate::stats_exporter::export_statistics;
 
 
 fn main() -> Result<()>  {
     let start = Instant::now();
     let (param, file_list) = Parameters::obtain();
     param.print();
-    let parsed_files_list: Vec<PathBuf> = file_list.clone().into_iter().map(|path| path.with_extension("_altered.csv")).collect();
-
-    extract_columns_base(&file_list, &parsed_files_list, &param);
-    let loaded_data = LoadedData::load_file_data(&parsed_files_list, &param).unwrap();
-    generate_plots(&loaded_data, &param)?;
-    let statistics = Statistics::calculate_statistics(&loaded_data);
-    generate_html(&statistics, &param)?;
+    match param.command {
+        Command::History => generate_history(&param)?,
+        Command::Index => generate_results_index(&param)?,
+        Command::Analyse => analyse(&param, &file_list)?
+    }
 
     info!("Done! Program execution duration: {:?}", start.elapsed());
 
     Ok(())
 }
 
+fn analyse(param: &Parameters, file_list: &[PathBuf]) -> Result<()> {
+    let parsed_files_list: Vec<PathBuf> = file_list.iter().map(|path| path.with_extension("_altered.csv")).collect();
+
+    extract_columns_base(file_list, &parsed_files_list, param);
+    let mut loaded_data = LoadedData::load_file_data(&parsed_files_list, param).unwrap();
+    resample_data(&mut loaded_data, param)?;
+    let metric_options = MetricOptionsList::load(&param.wanted_metrics_file)?;
+    let annotations = Annotations::load(param)?;
+    let charts = load_charts(&loaded_data, param)?;
+    let statistics = Statistics::calculate_statistics(&loaded_data, &metric_options, param);
+    generate_plots(&loaded_data, &statistics, &metric_options, &annotations, &charts, param)?;
+    generate_html(&loaded_data, &statistics, &metric_options, &charts, param)?;
+    generate_markdown(&loaded_data, &statistics, param)?;
+    export_statistics(&loaded_data, &statistics, file_list, param)?;
+    export_junit(&statistics, &metric_options, param)?;
+    record_run(&loaded_data, &statistics, file_list, param)?;
+    print_summary(&statistics);
+
+    Ok(())
+}
+
 trait FileName {
     fn get_file_name(&self, extension: &str) -> String;
 }
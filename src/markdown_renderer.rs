@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::time::Instant;
+use std::path::Path;
+use std::io::prelude::*;
+use handlebars::{Handlebars, no_escape, to_json};
+use anyhow::{Context, Result};
+use log::{debug, info};
+
+use crate::data_loader::LoadedData;
+use crate::formatting::format_unit;
+use crate::html_renderer::register_helpers;
+use crate::parameters::{Parameters, verify_file_exists};
+use crate::statistics::Statistics;
+
+// Widest metric and file names of the terminal table, longer names keep their end
+static TERMINAL_METRIC_WIDTH: usize = 50;
+static TERMINAL_FILE_WIDTH: usize = 24;
+
+
+// Compact summary.md for pull requests and chat, with the stats of every metric and the top
+// regressions over the first file
+pub fn generate_markdown(loaded_data: &LoadedData, statistics: &Statistics, param: &Parameters) -> Result<()> {
+    let start = Instant::now();
+    info!("Generating Markdown summary..");
+    let template_location = verify_file_exists(&"templates/summary.md.hbs".to_string());
+
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(no_escape);
+    handlebars.register_template_file("summary", &template_location)
+        .with_context(|| format!("Could not register handlebars template {:?}", &template_location))?;
+    register_helpers(&mut handlebars);
+
+    let mut data = statistics.jsonify();
+    let file_datas = loaded_data.get_all_data();
+    if file_datas.len() > 1 {
+        let baseline = &file_datas[0].file_name;
+        data.insert("baseline".to_string(), to_json(baseline));
+        data.insert("regression".to_string(), to_json(statistics.regressions(baseline)));
+    }
+
+    let summary_path = Path::new(&param.target_directory).join("summary.md");
+    let summary_content = handlebars.render("summary", &data)
+        .with_context(|| format!("Could not render the template {:?}", &template_location))?;
+    let mut summary_file = File::create(&summary_path)
+        .with_context(|| format!("Could not create file {:?}", &summary_path))?;
+    summary_file.write_all(summary_content.as_bytes())
+        .with_context(|| format!("Could not write to file {:?}", summary_path))?;
+
+    debug!("Sequencial Markdown generation: {:?}", start.elapsed());
+    Ok(())
+}
+
+// Plain text table of the key stats, printed to stdout at the end of a run
+pub fn print_summary(statistics: &Statistics) {
+    let header = ["Metric", "File", "Average", "90th", "99th", "Trend/hour"];
+    let rows: Vec<[String; 6]> = statistics.overview().iter()
+        .map(|row| [
+            shorten(row.name, TERMINAL_METRIC_WIDTH),
+            shorten(row.file_name, TERMINAL_FILE_WIDTH),
+            format_unit(row.average, row.unit),
+            format_unit(row.p90th_percentile, row.unit),
+            format_unit(row.p99th_percentile, row.unit),
+            format!("{}{}", if row.significant { "*" } else { "" }, format_unit(row.growth_per_hour, row.unit))
+        ])
+        .collect();
+
+    let mut widths = header.map(|title| title.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: &[String]| -> String {
+        cells.iter().zip(&widths).enumerate()
+            .map(|(idx, (cell, width))| match idx {
+                // Names are aligned to the left, values to the right
+                0 | 1 => format!("{:<width$}", cell, width = width),
+                _ => format!("{:>width$}", cell, width = width)
+            })
+            .collect::<Vec<String>>()
+            .join("  ")
+    };
+
+    println!();
+    println!("{}", line(&header.map(String::from)));
+    println!("{}", widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<String>>().join("  "));
+    for row in &rows {
+        println!("{}", line(row));
+    }
+    println!("(* significant trend)");
+    println!();
+}
+
+fn shorten(text: &str, width: usize) -> String {
+    let amount = text.chars().count();
+    if amount <= width {
+        return text.to_string();
+    }
+
+    format!("...{}", text.chars().skip(amount - width + 3).collect::<String>())
+}
@@ -0,0 +1,321 @@
+use anyhow::{bail, Context, Result};
+use log::debug;
+use std::io::{self, BufRead};
+use std::fs::File;
+use std::path::PathBuf;
+
+use crate::formatting::infer_unit;
+
+// Per metric options, written in the wanted metrics file after a '|':
+//     Process(w3wp)\Private Bytes | anomaly: mad, anomaly_threshold: 5
+// Every wanted metric line that is contained in a metric name applies its options, in file order.
+pub struct MetricOptionsList {
+    rules: Vec<(String, Vec<(String, String)>)>
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricOptions {
+    pub anomaly: AnomalyDetector,
+    // Score above which a sample is flagged
+    pub anomaly_threshold: f64,
+    // Amount of previous samples used as the baseline
+    pub anomaly_window: usize,
+    pub changepoint: ChangePointDetector,
+    // Multiplied by the logarithm of the amount of samples, higher values find fewer changes
+    pub changepoint_penalty: f64,
+    pub changepoint_min_segment: usize,
+    // Y axis of the line chart
+    pub y_scale: AxisScale,
+    pub y_min: AxisLimit,
+    pub y_max: AxisLimit,
+    // Percentile of all the values above which samples are drawn on the top of the chart and marked
+    pub y_clip_percentile: Option<f64>,
+    // Unit of the values, inferred from the counter name when not set
+    pub unit: Option<String>,
+    // Which way the values improve, so that only changes the other way are regressions
+    pub better: Better,
+    // Accepted values of the statistics, checked in junit.xml
+    pub limits: Vec<Limit>
+}
+
+#[derive(Debug, Clone)]
+pub enum AnomalyDetector {
+    ZScore,
+    Mad,
+    None
+}
+
+#[derive(Debug, Clone)]
+pub enum ChangePointDetector {
+    Pelt,
+    None
+}
+
+#[derive(Debug, Clone)]
+pub enum AxisScale {
+    Linear,
+    Log
+}
+
+// Auto follows the lowest or highest value of the metric
+#[derive(Debug, Clone)]
+pub enum AxisLimit {
+    Auto,
+    Fixed(f64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Better {
+    Lower,
+    // Throughput and free resources, such as Requests/sec or Available MBytes
+    Higher
+}
+
+// Highest or lowest accepted value of a statistic, written as max_p90: 80 or min_average: 10
+#[derive(Debug, Clone)]
+pub struct Limit {
+    pub statistic: LimitStatistic,
+    pub bound: LimitBound,
+    pub value: f64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitStatistic {
+    Average,
+    Median,
+    P1,
+    P25,
+    P75,
+    P90,
+    P99,
+    GrowthPerHour,
+    Anomalies,
+    ChangePoints
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitBound {
+    Max,
+    Min
+}
+
+impl LimitStatistic {
+
+    fn parse(name: &str) -> Option<LimitStatistic> {
+        match name {
+            "average" => Some(LimitStatistic::Average),
+            "median" => Some(LimitStatistic::Median),
+            "p1" => Some(LimitStatistic::P1),
+            "p25" => Some(LimitStatistic::P25),
+            "p75" => Some(LimitStatistic::P75),
+            "p90" => Some(LimitStatistic::P90),
+            "p99" => Some(LimitStatistic::P99),
+            "growth_per_hour" => Some(LimitStatistic::GrowthPerHour),
+            "anomalies" => Some(LimitStatistic::Anomalies),
+            "change_points" => Some(LimitStatistic::ChangePoints),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LimitStatistic::Average => "average",
+            LimitStatistic::Median => "median",
+            LimitStatistic::P1 => "p1",
+            LimitStatistic::P25 => "p25",
+            LimitStatistic::P75 => "p75",
+            LimitStatistic::P90 => "p90",
+            LimitStatistic::P99 => "p99",
+            LimitStatistic::GrowthPerHour => "growth_per_hour",
+            LimitStatistic::Anomalies => "anomalies",
+            LimitStatistic::ChangePoints => "change_points"
+        }
+    }
+
+    // Anomalies and change points are amounts, without the unit of the metric
+    pub fn has_unit(&self) -> bool {
+        !matches!(self, LimitStatistic::Anomalies | LimitStatistic::ChangePoints)
+    }
+}
+
+impl Limit {
+
+    pub fn option_name(&self) -> String {
+        match self.bound {
+            LimitBound::Max => format!("max_{}", self.statistic.name()),
+            LimitBound::Min => format!("min_{}", self.statistic.name())
+        }
+    }
+
+    pub fn accepts(&self, value: f64) -> bool {
+        match self.bound {
+            LimitBound::Max => value <= self.value,
+            LimitBound::Min => value >= self.value
+        }
+    }
+}
+
+impl Default for MetricOptions {
+    fn default() -> MetricOptions {
+        MetricOptions {
+            anomaly: AnomalyDetector::ZScore,
+            anomaly_threshold: 4.0,
+            anomaly_window: 60,
+            changepoint: ChangePointDetector::Pelt,
+            changepoint_penalty: 10.0,
+            changepoint_min_segment: 30,
+            y_scale: AxisScale::Linear,
+            y_min: AxisLimit::Fixed(0.0),
+            y_max: AxisLimit::Auto,
+            y_clip_percentile: None,
+            unit: None,
+            better: Better::Lower,
+            limits: Vec::new()
+        }
+    }
+}
+
+impl MetricOptions {
+
+    pub fn unit(&self, metric: &str) -> String {
+        match &self.unit {
+            Some(unit) => unit.clone(),
+            None => infer_unit(metric).to_string()
+        }
+    }
+
+    fn apply(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "anomaly" => self.anomaly = match value {
+                "zscore" => AnomalyDetector::ZScore,
+                "mad" => AnomalyDetector::Mad,
+                "none" => AnomalyDetector::None,
+                _ => bail!("Wrong anomaly detector {}. Options are <zscore>, <mad> or <none>", value)
+            },
+            "anomaly_threshold" => self.anomaly_threshold = value.parse::<f64>()
+                .with_context(|| format!("Invalid anomaly_threshold {}", value))?,
+            "anomaly_window" => self.anomaly_window = value.parse::<usize>()
+                .with_context(|| format!("Invalid anomaly_window {}", value))?,
+            "changepoint" => self.changepoint = match value {
+                "pelt" => ChangePointDetector::Pelt,
+                "none" => ChangePointDetector::None,
+                _ => bail!("Wrong change point detector {}. Options are <pelt> or <none>", value)
+            },
+            "changepoint_penalty" => self.changepoint_penalty = value.parse::<f64>()
+                .with_context(|| format!("Invalid changepoint_penalty {}", value))?,
+            "changepoint_min_segment" => self.changepoint_min_segment = value.parse::<usize>()
+                .with_context(|| format!("Invalid changepoint_min_segment {}", value))?,
+            "y_scale" => self.y_scale = match value {
+                "linear" => AxisScale::Linear,
+                "log" => AxisScale::Log,
+                _ => bail!("Wrong y_scale {}. Options are <linear> or <log>", value)
+            },
+            "y_min" => self.y_min = parse_axis_limit(key, value)?,
+            "y_max" => self.y_max = parse_axis_limit(key, value)?,
+            "y_clip_percentile" => self.y_clip_percentile = match value {
+                "none" => None,
+                _ => {
+                    let percentile = value.parse::<f64>()
+                        .with_context(|| format!("Invalid y_clip_percentile {}", value))?;
+                    if percentile <= 0.0 || percentile > 100.0 {
+                        bail!("y_clip_percentile {} should be above 0 and at most 100", value);
+                    }
+                    Some(percentile)
+                }
+            },
+            "unit" => self.unit = match value {
+                "auto" => None,
+                "none" => Some(String::new()),
+                _ => Some(value.to_string())
+            },
+            "better" => self.better = match value {
+                "lower" => Better::Lower,
+                "higher" => Better::Higher,
+                _ => bail!("Wrong better {}. Options are <lower> or <higher>", value)
+            },
+            _ if key.starts_with("max_") || key.starts_with("min_") => {
+                let bound = if key.starts_with("max_") { LimitBound::Max } else { LimitBound::Min };
+                let statistic = LimitStatistic::parse(&key[4..])
+                    .with_context(|| format!("Unknown limit {}. Statistics are <average>, <median>, <p1>, <p25>, <p75>, <p90>, <p99>, <growth_per_hour>, <anomalies> or <change_points>", key))?;
+                // A later line replaces the limit, or removes it with none
+                self.limits.retain(|limit| limit.statistic != statistic || limit.bound != bound);
+                if value != "none" {
+                    let value = value.parse::<f64>()
+                        .with_context(|| format!("Invalid {} {}. Options are <none> or a number", key, value))?;
+                    self.limits.push(Limit { statistic, bound, value });
+                }
+            },
+            _ => bail!("Unknown metric option {}", key)
+        }
+
+        Ok(())
+    }
+}
+
+impl MetricOptionsList {
+
+    pub fn load(wanted_metrics_location: &PathBuf) -> Result<MetricOptionsList> {
+        let mut rules = Vec::new();
+        let file = File::open(wanted_metrics_location)
+            .with_context(|| format!("Could not open file {:?}", wanted_metrics_location))?;
+        let reader = io::BufReader::new(file).lines();
+
+        for line in reader.map_while(Result::ok) {
+            if line == "#$%#$%THIS_IS_THE_SEPARATOR. UP ARE WANTED METRICS, BELOW ARE IGNORED METRICS." {
+                break;
+            }
+
+            let (pattern, options) = match split_metric_line(&line) {
+                (pattern, Some(options)) => (pattern, options),
+                (_, None) => continue
+            };
+
+            let mut parsed_options = Vec::new();
+            for option in options.split(',').filter(|option| !option.trim().is_empty()) {
+                let key_value: Vec<&str> = option.splitn(2, ':').map(|s| s.trim()).collect();
+                if key_value.len() != 2 {
+                    bail!("Metric option {:?} of {} should be written as <key: value>", option, pattern);
+                }
+
+                // Validate right away, so that a typo is reported once instead of per metric
+                MetricOptions::default().apply(key_value[0], key_value[1])
+                    .with_context(|| format!("Problem in the options of {}", pattern))?;
+                parsed_options.push((key_value[0].to_string(), key_value[1].to_string()));
+            }
+
+            rules.push((pattern.to_string(), parsed_options));
+        }
+
+        debug!("Metric options: {:?}", rules);
+        Ok(MetricOptionsList { rules })
+    }
+
+    pub fn get(&self, metric: &str) -> MetricOptions {
+        let mut options = MetricOptions::default();
+        for (pattern, pattern_options) in &self.rules {
+            if metric.contains(pattern.as_str()) {
+                for (key, value) in pattern_options {
+                    options.apply(key, value).unwrap();
+                }
+            }
+        }
+
+        options
+    }
+}
+
+fn parse_axis_limit(key: &str, value: &str) -> Result<AxisLimit> {
+    match value {
+        "auto" => Ok(AxisLimit::Auto),
+        _ => Ok(AxisLimit::Fixed(value.parse::<f64>()
+            .with_context(|| format!("Invalid {} {}. Options are <auto> or a number", key, value))?))
+    }
+}
+
+// Splits a wanted metrics line into the metric text and its options, if any
+pub fn split_metric_line(line: &str) -> (&str, Option<&str>) {
+    match line.find('|') {
+        Some(idx) => (line[..idx].trim_end(), Some(&line[idx + 1..])),
+        None => (line, None)
+    }
+}
@@ -7,37 +7,131 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 
 pub enum TimeFormat {
+    Milliseconds,
     Seconds,
-    Minutes
+    Minutes,
+    Hours,
+    // Time of day of the samples, instead of the time since the first one
+    Clock
 }
+
+pub enum Aggregation {
+    Mean,
+    Max,
+    Last
+}
+
+pub enum ImageFormat {
+    Png,
+    Svg,
+    Both
+}
+
+// How the lines of the plots are reduced to about one point per pixel
+pub enum Downsampling {
+    // Largest-Triangle-Three-Buckets
+    Lttb,
+    // Lowest and highest sample of every pixel
+    MinMax,
+    None
+}
+
+// What Napal does, given as the first argument. Without a command the csv files are analysed.
+pub enum Command {
+    Analyse,
+    // Trend charts of the statistics recorded in the history database
+    History,
+    // Index page of the reports found in the results directory
+    Index
+}
+
+pub enum CorrelationMethod {
+    Pearson,
+    Spearman
+}
+
+pub struct ResampleSettings {
+    // Grid interval in seconds, no resampling when None
+    pub interval: Option<f64>,
+    pub aggregation: Aggregation,
+    // Metric used as the alignment marker, and the value it must exceed
+    pub align_metric: Option<String>,
+    pub align_threshold: f64
+}
+
+impl ImageFormat {
+
+    // Extension of the images referenced by the reports
+    pub fn report_extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => ".png",
+            ImageFormat::Svg | ImageFormat::Both => ".svg"
+        }
+    }
+}
+
 pub struct Parameters {
+    pub command: Command,
     pub base_directory: PathBuf,
     pub skip_parse: bool,
-    pub width_per_point: u32,
+    // When set, plots grow with the amount of points instead of being downsampled
+    pub width_per_point: Option<u32>,
     pub target_directory: PathBuf,
     pub x_axis: TimeFormat,
     pub data_time_format: String,
     pub wanted_metrics_file: PathBuf,
     pub plotter_config_file: PathBuf,
-    pub plotter_colors_file: PathBuf
+    pub plotter_colors_file: PathBuf,
+    pub charts_file: PathBuf,
+    pub annotations_file: PathBuf,
+    pub template_file: PathBuf,
+    pub events_file: Option<PathBuf>,
+    pub resample: ResampleSettings,
+    pub correlation_method: CorrelationMethod,
+    pub image_format: ImageFormat,
+    pub downsampling: Downsampling,
+    // Whether the report embeds its stylesheet and images
+    pub single_file: bool,
+    // Whether the metric plots of the report are zoomable charts drawn in the browser
+    pub interactive: bool,
+    // SQLite database where every analysis records its statistics
+    pub history_file: PathBuf,
+    pub record_history: bool,
+    // Key and value pairs, such as build=1234, recorded with the run or selecting the runs of the history
+    pub tags: Vec<(String, String)>,
+    // Amount of latest runs shown by the history
+    pub history_runs: usize
 }
 
 static WANTED_METRICS_DEFAULT_PATH: &str = "config/DefaultMetrics.txt";
 static PLOTTER_CONFIG_DEFAULT_PATH: &str = "config/DefaultPlotSettings.txt";
 static PLOTTER_COLORS_DEFAULT_PATH: &str = "config/DefaultPlotLineColors.txt";
+static CHARTS_DEFAULT_PATH: &str = "config/DefaultCharts.txt";
+static ANNOTATIONS_DEFAULT_PATH: &str = "config/DefaultAnnotations.txt";
+static TEMPLATE_DEFAULT_PATH: &str = "templates/template.hbs";
+static HISTORY_DEFAULT_NAME: &str = "history.db";
 
 impl Parameters {
 
-    fn new(base_directory: PathBuf, skip_parse: bool, width_per_point: u32, target_directory: PathBuf, wanted_metrics_file: &String, 
-        x_axis: TimeFormat, data_time_format: String, plotter_config_file: &String, plotter_colors_file: &String) -> Result<Parameters, Box<dyn std::error::Error>> {
+    #[allow(clippy::too_many_arguments)]
+    fn new(command: Command, base_directory: PathBuf, skip_parse: bool, width_per_point: Option<u32>, target_directory: PathBuf, wanted_metrics_file: &String, 
+        x_axis: TimeFormat, data_time_format: String, plotter_config_file: &String, plotter_colors_file: &String,
+        charts_file: &String, annotations_file: &String, template_file: &String, events_file: Option<&String>, resample: ResampleSettings, correlation_method: CorrelationMethod, image_format: ImageFormat,
+        downsampling: Downsampling, single_file: bool, interactive: bool, history_file: PathBuf, record_history: bool, tags: Vec<(String, String)>,
+        history_runs: usize) -> Result<Parameters, Box<dyn std::error::Error>> {
         fs::create_dir_all(&target_directory)
             .with_context(|| format!("Could not create directory {:?}", &target_directory))?;
 
         let verified_plotter_config_file = verify_file_exists(plotter_config_file);
         let verified_plotter_colors_file = verify_file_exists(plotter_colors_file);
         let verified_wanted_metrics_file = verify_file_exists(wanted_metrics_file);
+        let verified_charts_file = verify_file_exists(charts_file);
+        let verified_annotations_file = verify_file_exists(annotations_file);
+        let verified_template_file = verify_file_exists(template_file);
+        let verified_events_file = events_file.map(verify_file_exists);
         
         Ok(Parameters {
+            command,
             base_directory,
             skip_parse,
             width_per_point,
@@ -47,6 +141,20 @@ impl Parameters {
             data_time_format,
             plotter_config_file: verified_plotter_config_file,
             plotter_colors_file: verified_plotter_colors_file,
+            charts_file: verified_charts_file,
+            annotations_file: verified_annotations_file,
+            template_file: verified_template_file,
+            events_file: verified_events_file,
+            resample,
+            correlation_method,
+            image_format,
+            downsampling,
+            single_file,
+            interactive,
+            history_file,
+            record_history,
+            tags,
+            history_runs
         })
     }
 
@@ -66,37 +174,65 @@ impl Parameters {
         let base_path = Parameters::get_base_path();
 
         let mut skip_parse = false;
-        let mut width_per_point = 1;
+        let mut width_per_point = None;
         let now = Utc::now();
         // Default target directory is based on time
+        let mut target_directory_given = false;
         let mut target_directory = PathBuf::from("results/").join(format!("{}-{}-{}_{}-{}-{}", now.year(), now.month(), now.day(), now.hour(), now.minute(), now.second()));
-        let mut data_time_format = "%m/%d/%Y %H:%M:%S.%f".to_string();
+        // %.f reads the fraction as a fraction of a second, .%f would read it as nanoseconds
+        let mut data_time_format = "%m/%d/%Y %H:%M:%S%.f".to_string();
         let mut file_list: Vec<String> = Vec::new();
         let mut x_axis = TimeFormat::Seconds;
         let mut wanted_metrics_file: &String = &WANTED_METRICS_DEFAULT_PATH.to_string();
         let mut plotter_config_file: &String = &PLOTTER_CONFIG_DEFAULT_PATH.to_string();
         let mut plotter_colors_file: &String = &PLOTTER_COLORS_DEFAULT_PATH.to_string();
+        let mut charts_file: &String = &CHARTS_DEFAULT_PATH.to_string();
+        let mut annotations_file: &String = &ANNOTATIONS_DEFAULT_PATH.to_string();
+        let mut template_file: &String = &TEMPLATE_DEFAULT_PATH.to_string();
+        let mut events_file: Option<&String> = None;
         let mut verbose = false;
+        let mut resample = ResampleSettings {
+            interval: None,
+            aggregation: Aggregation::Mean,
+            align_metric: None,
+            align_threshold: 0.0
+        };
+        let mut correlation_method = CorrelationMethod::Pearson;
+        let mut image_format = ImageFormat::Png;
+        let mut downsampling = Downsampling::Lttb;
+        let mut single_file = false;
+        let mut interactive = false;
+        let mut history_file = None;
+        let mut record_history = true;
+        let mut tags = Vec::new();
+        let mut history_runs = 50;
     
         let args: Vec<String> = env::args().skip(1).collect();
 
-        if args.len() == 0 {
+        if args.is_empty() {
             Parameters::help();
             std::process::exit(0) 
         }
-    
-        let mut i = 0;
+
+        let (command, mut i) = match args[0].to_lowercase().as_str() {
+            "history" => (Command::History, 1),
+            "index" => (Command::Index, 1),
+            _ => (Command::Analyse, 0)
+        };
         while i < args.len() {
             let current_arg = &args[i];
 
             match current_arg.to_lowercase().as_str() {
                 "-s" | "-skipparse" => skip_parse = true,
+                "-sf" | "-singlefile" | "--single-file" => single_file = true,
+                "-i" | "-interactive" => interactive = true,
                 "-w" | "-widthperpoint" => { 
-                    width_per_point = args.get(i + 1).unwrap().parse::<u32>().unwrap();
+                    width_per_point = Some(args.get(i + 1).unwrap().parse::<u32>().unwrap());
                     i += 1
                 }
                 "-t" | "-targetdir" => {
                     target_directory = PathBuf::from(args.get(i + 1).unwrap().clone());
+                    target_directory_given = true;
                     i += 1
                 }
                 "-tf" | "-timeformat" => {
@@ -112,6 +248,39 @@ impl Parameters {
                     plotter_colors_file = args.get(i + 1).unwrap();
                     i += 1
                 }
+                "-ch" | "-charts" => {
+                    charts_file = args.get(i + 1).unwrap();
+                    i += 1
+                }
+                "-an" | "-annotations" => {
+                    annotations_file = args.get(i + 1).unwrap();
+                    i += 1
+                }
+                "-tp" | "-template" | "--template" => {
+                    template_file = args.get(i + 1).unwrap();
+                    i += 1
+                }
+                "-ev" | "-events" => {
+                    events_file = Some(args.get(i + 1).unwrap());
+                    i += 1
+                }
+                "-db" | "-database" => {
+                    history_file = Some(PathBuf::from(args.get(i + 1).unwrap().clone()));
+                    i += 1
+                }
+                "-nh" | "-nohistory" => record_history = false,
+                "-tag" => {
+                    let tag = args.get(i + 1).unwrap();
+                    match tag.find('=') {
+                        Some(idx) if idx > 0 => tags.push((tag[..idx].to_string(), tag[idx + 1..].to_string())),
+                        _ => panic!("Wrong tag {}. Tags are written as <key=value>", tag)
+                    }
+                    i += 1
+                }
+                "-last" => {
+                    history_runs = args.get(i + 1).unwrap().parse::<usize>().unwrap();
+                    i += 1
+                }
                 "-wm" | "-wantedmetrics" => {
                     wanted_metrics_file = args.get(i + 1).unwrap();
                     i += 1
@@ -126,12 +295,66 @@ impl Parameters {
                 "-xaxis" => {
                     let time_format_arg = args.get(i + 1).unwrap().clone();
                     x_axis = match time_format_arg.to_lowercase().as_str() {
+                        "milliseconds" => TimeFormat::Milliseconds,
                         "seconds" => TimeFormat::Seconds,
                         "minutes" => TimeFormat::Minutes,
-                        _ => panic!("Wrong time format. Options are <seconds> or <minutes>")
+                        "hours" => TimeFormat::Hours,
+                        "clock" => TimeFormat::Clock,
+                        _ => panic!("Wrong time format. Options are <milliseconds>, <seconds>, <minutes>, <hours> or <clock>")
                     };
                     i += 1    
                 }
+                "-r" | "-resample" => {
+                    resample.interval = Some(args.get(i + 1).unwrap().parse::<f64>().unwrap());
+                    i += 1
+                }
+                "-ag" | "-aggregation" => {
+                    let aggregation_arg = args.get(i + 1).unwrap().clone();
+                    resample.aggregation = match aggregation_arg.to_lowercase().as_str() {
+                        "mean" => Aggregation::Mean,
+                        "max" => Aggregation::Max,
+                        "last" => Aggregation::Last,
+                        _ => panic!("Wrong aggregation. Options are <mean>, <max> or <last>")
+                    };
+                    i += 1
+                }
+                "-am" | "-alignmetric" => {
+                    resample.align_metric = Some(args.get(i + 1).unwrap().clone());
+                    i += 1
+                }
+                "-at" | "-alignthreshold" => {
+                    resample.align_threshold = args.get(i + 1).unwrap().parse::<f64>().unwrap();
+                    i += 1
+                }
+                "-f" | "-format" => {
+                    let format_arg = args.get(i + 1).unwrap().clone();
+                    image_format = match format_arg.to_lowercase().as_str() {
+                        "png" => ImageFormat::Png,
+                        "svg" => ImageFormat::Svg,
+                        "both" => ImageFormat::Both,
+                        _ => panic!("Wrong image format. Options are <png>, <svg> or <both>")
+                    };
+                    i += 1
+                }
+                "-ds" | "-downsample" => {
+                    let downsample_arg = args.get(i + 1).unwrap().clone();
+                    downsampling = match downsample_arg.to_lowercase().as_str() {
+                        "lttb" => Downsampling::Lttb,
+                        "minmax" => Downsampling::MinMax,
+                        "none" => Downsampling::None,
+                        _ => panic!("Wrong downsampling method. Options are <lttb>, <minmax> or <none>")
+                    };
+                    i += 1
+                }
+                "-cm" | "-correlationmethod" => {
+                    let correlation_arg = args.get(i + 1).unwrap().clone();
+                    correlation_method = match correlation_arg.to_lowercase().as_str() {
+                        "pearson" => CorrelationMethod::Pearson,
+                        "spearman" => CorrelationMethod::Spearman,
+                        _ => panic!("Wrong correlation method. Options are <pearson> or <spearman>")
+                    };
+                    i += 1
+                }
 
                 _ => file_list.push(current_arg.to_owned())
             }
@@ -139,12 +362,28 @@ impl Parameters {
             i += 1;
         }
 
+        // The index is written to the results directory it lists
+        if let Command::Index = command {
+            target_directory = match (file_list.pop(), target_directory_given) {
+                (Some(_), true) => panic!("The index takes its results directory either as an argument or with -t, not both"),
+                (Some(results_directory), false) => PathBuf::from(results_directory),
+                (None, true) => target_directory,
+                (None, false) => PathBuf::from("results/")
+            };
+        }
+        // The default database sits next to the report directories, results/history.db by default
+        let history_file = history_file.unwrap_or_else(|| match target_directory.parent() {
+            Some(parent) => parent.join(HISTORY_DEFAULT_NAME),
+            None => PathBuf::from(HISTORY_DEFAULT_NAME)
+        });
+
         Builder::new()
             .format_timestamp(Option::None)
             .format_module_path(false)
             .filter_level(if verbose { LevelFilter::max()} else { LevelFilter::Info}).init();
 
         let param = Parameters::new(
+            command,
             base_path,
             skip_parse, 
             width_per_point, 
@@ -154,6 +393,20 @@ impl Parameters {
             data_time_format,
             plotter_config_file,
             plotter_colors_file,
+            charts_file,
+            annotations_file,
+            template_file,
+            events_file,
+            resample,
+            correlation_method,
+            image_format,
+            downsampling,
+            single_file,
+            interactive,
+            history_file,
+            record_history,
+            tags,
+            history_runs
         ).unwrap();
 
         let verified_file_list = file_list.into_iter().map(|file_string| verify_file_exists(&file_string)).collect::<Vec<PathBuf>>();
@@ -162,7 +415,7 @@ impl Parameters {
     }
 
     pub fn help() {
-        println!("{}", r"
+        let help_text = r"
 Napal
 
 A faster Performance Analysis of Logs
@@ -180,6 +433,15 @@ Examples:
     .\napal.exe testfile1.csv testfile2.csv
         This will use the default settings and create plots with two lines. This allows the comparison of different executions of the same thing.
 
+    .\napal.exe -tag build=1234 -tag branch=main testfile.csv
+        This will also record the statistics in the history database, tagged with the build and the branch.
+
+    .\napal.exe history -tag branch=main -last 50
+        This will create trend charts of the statistics of the last 50 recorded runs of the main branch.
+
+    .\napal.exe index results
+        This will list every report of the results directory, with its inputs, date, tags and headline stats, in results/index.htm.
+
         
 Parameters:
 
@@ -209,9 +471,30 @@ Parameters:
     The path for the file that contains a list of the colors to be used in the graphs (by order)
     Default is config/DefaultPlotLineColors.txt.
 
+[-ch or -charts]
+    The path for the file that defines charts combining several metrics, drawn on one plot (with an optional right Y axis) or as a grid of subplots.
+    Default is config/DefaultCharts.txt.
+
+[-an or -annotations]
+    The path for the file with the reference lines (line: metric | value | label | colour) and events (event: time | label | colour) drawn on the metric plots.
+    Default is config/DefaultAnnotations.txt.
+
+[-tp or -template or --template <path>]
+    The path for the handlebars template of the report. Partials in a partials/ directory next to it replace the default ones with the same name.
+    Default is templates/template.hbs.
+
+[-ev or -events]
+    The path for a file with one event per line, written as <time>,<label>. The time is a timestamp in the time format or the seconds since the start of each file.
+    Default is to not read an events file.
+
 [-w or -widthPerPoint]
-    The width of the generated image per given point in each graph (x axis).
-    Default is 1.
+    The width of the generated image per given point in each graph (x axis). The plots are not downsampled when it is set.
+    Default is to use the minimum_width of the plot settings and downsample the lines.
+
+[-ds or -downsample (lttb|minmax|none)]
+    How the lines are reduced to about one point per pixel, so long captures keep a fixed width.
+    lttb (Largest-Triangle-Three-Buckets) keeps the shape of the line, minmax keeps the lowest and highest sample of every pixel.
+    Default is lttb.
 
 [-s or -skipParse]
     Whether the .csv files should be parsed or not. It is required for them to be parsed at least once, so that they generate the .altered.csv file.
@@ -219,15 +502,73 @@ Parameters:
 
 [-tf or -timeFormat]
     The format for the date column. How to write the format: https://docs.rs/chrono/0.4.7/chrono/format/strftime/index.html
-    Default is %m/%d/%Y %H:%M:%S.%f 
+    Default is %m/%d/%Y %H:%M:%S%.f
         Example date: 05/02/2020 15:30:10.012.
 
-[-xaxis (seconds|minutes)]
-    Whether the X axis of the plots should be in seconds or minutes.
+[-xaxis (milliseconds|seconds|minutes|hours|clock)]
+    The unit of the X axis of the plots, as time since the first sample of each file.
+    clock places the samples at their time of day instead, so files recorded at different times do not overlap.
     Default is seconds.
     Example: 
         .\napal.exe -xaxis seconds testfile1.csv
         .\napal.exe -xaxis minutes testfile1.csv
+        .\napal.exe -xaxis clock testfile1.csv
+
+[-r or -resample <seconds>]
+    Resamples every file onto a common grid with the given interval (fractions allowed), so files can be compared sample by sample.
+    The grid is in time since the start of each file (or its marker), samples that go back before the first one are skipped.
+    Default is to not resample.
+
+[-ag or -aggregation (mean|max|last)]
+    How the samples that fall in the same grid interval are combined when resampling.
+    Default is mean.
+
+[-am or -alignMetric <metric>]
+    Aligns every file on a marker event instead of its first sample. The marker is the first sample where the metric that contains the given text is above the align threshold.
+    Samples before the marker are discarded.
+    Default is to align on the first sample.
+
+[-at or -alignThreshold <value>]
+    The value the align metric must exceed for the marker event.
+    Default is 0.
+
+[-f or -format (png|svg|both)]
+    The format of the generated images. The report uses the SVG images when both are generated.
+    Default is png.
+
+[-cm or -correlationMethod (pearson|spearman)]
+    The correlation used for the heatmap of each file and to pick the top correlated metrics. Both coefficients are shown in the report.
+    Default is pearson.
+
+[-sf or -singleFile or --single-file]
+    Writes the report as one portable HTML file, with the stylesheet, the scripts and the images embedded, so it can be emailed or attached to a ticket.
+    Default is to link the stylesheet, the scripts and the images next to index.htm.
+
+[-i or -interactive]
+    Draws the metric plots of the report in the browser, where they can be zoomed by dragging, hovered for the values and have lines hidden from the legend. The images are still generated and shown when scripts are disabled.
+    Default is to only show the images.
+
+[-db or -database <path>]
+    The SQLite database where every analysis records its run, tags and statistics, and that the history command reads.
+    Default is history.db in the parent directory of the target directory, which is results/history.db without -t.
+
+[-nh or -noHistory]
+    Does not record the analysis in the history database.
+    Default is to record it.
+
+[-tag <key=value>]
+    A tag of the run, such as build=1234, branch=main or environment=staging. Can be given several times.
+    The history command only shows the runs that have every given tag.
+    Default is no tags.
+
+history [-last <amount>]
+    Given as the first argument, creates a report with the trend of the average, 90th and 99th percentile of every metric across the recorded runs, instead of analysing csv files.
+    -last is the amount of latest runs shown, default is 50.
+
+index [results directory]
+    Given as the first argument, writes an index.htm to the results directory that lists and links every report in its directories, newest first, instead of analysing csv files.
+    The results directory can also be given with -t, but not both ways at once.
+    Default directory is results.
 
 [-v or -verbose]
     Whether to display debug information.
@@ -235,7 +576,8 @@ Parameters:
 
 [-h or -help]
     Displays this information.        
-        ");
+        ";
+        println!("{}", help_text);
     }
 
     pub fn print(&self) {
@@ -249,14 +591,61 @@ Parameters:
         info!("     The analysed metrics file is {:?}.", self.wanted_metrics_file);
         info!("     The Plot config file is {:?}.", self.plotter_config_file);
         info!("     The colors file is {:?}.", self.plotter_colors_file);
+        info!("     The charts file is {:?}.", self.charts_file);
+        info!("     The annotations file is {:?}.", self.annotations_file);
+        info!("     The report template is {:?}.", self.template_file);
+        if let Some(events_file) = &self.events_file {
+            info!("     The events file is {:?}.", events_file);
+        }
         info!("     Target directory is {:?}.", self.target_directory);
         info!("Other configs:");
-        info!("     Width per point is {}.", self.width_per_point);
+        match (self.width_per_point, &self.downsampling) {
+            (Some(width_per_point), _) => info!("     Width per point is {}.", width_per_point),
+            (None, Downsampling::Lttb) => info!("     Plot lines will be downsampled with LTTB."),
+            (None, Downsampling::MinMax) => info!("     Plot lines will be downsampled to the min and max of every pixel."),
+            (None, Downsampling::None) => info!("     Plot lines will not be downsampled.")
+        }
         info!("     The data time format is {}.", self.data_time_format);
         match self.x_axis {
+            TimeFormat::Milliseconds => info!("     Plot X axis will be in milliseconds."),
             TimeFormat::Seconds => info!("     Plot X axis will be in seconds."),
-            TimeFormat::Minutes => info!("     Plot X axis will be in minutes.")
+            TimeFormat::Minutes => info!("     Plot X axis will be in minutes."),
+            TimeFormat::Hours => info!("     Plot X axis will be in hours."),
+            TimeFormat::Clock => info!("     Plot X axis will be the time of day.")
+        }
+        if let Some(interval) = self.resample.interval {
+            match self.resample.aggregation {
+                Aggregation::Mean => info!("     Data will be resampled every {} seconds using the mean.", interval),
+                Aggregation::Max => info!("     Data will be resampled every {} seconds using the max.", interval),
+                Aggregation::Last => info!("     Data will be resampled every {} seconds using the last value.", interval)
+            }
+        }
+        if let Some(align_metric) = &self.resample.align_metric {
+            info!("     Files will be aligned on the first sample of {} above {}.", align_metric, self.resample.align_threshold);
+        }
+        match self.image_format {
+            ImageFormat::Png => info!("     Images will be PNG."),
+            ImageFormat::Svg => info!("     Images will be SVG."),
+            ImageFormat::Both => info!("     Images will be PNG and SVG.")
+        }
+        match self.correlation_method {
+            CorrelationMethod::Pearson => info!("     Correlations will use the Pearson coefficient."),
+            CorrelationMethod::Spearman => info!("     Correlations will use the Spearman coefficient.")
+        }
+        if self.single_file {
+            info!("     The report will be a single file.");
+        }
+        if self.interactive {
+            info!("     The metric plots of the report will be interactive.");
+        }
+        let tags = self.tags.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<String>>().join(", ");
+        match self.command {
+            Command::Analyse if self.record_history => info!("     The statistics will be recorded in {:?} with the tags [{}].", self.history_file, tags),
+            Command::Analyse => info!("     The statistics will not be recorded."),
+            Command::History => info!("     The history will show the last {} runs of {:?} with the tags [{}].", self.history_runs, self.history_file, tags),
+            Command::Index => info!("     The reports of {:?} will be listed in its index.htm.", self.target_directory)
         }
+        debug!("Base directory is {:?}", self.base_directory);
         info!("");
     }
 }
@@ -271,13 +660,13 @@ pub fn verify_file_exists(file_path: &String) -> PathBuf {
         return possible_path;
     }
 
-    possible_path = env::current_exe().unwrap().parent().unwrap().join(&file_path);
+    possible_path = env::current_exe().unwrap().parent().unwrap().join(file_path);
 
     if possible_path.exists() {
         return possible_path;
     }
 
-    possible_path = env::current_exe().unwrap().parent().unwrap().parent().unwrap().parent().unwrap().join(&file_path);
+    possible_path = env::current_exe().unwrap().parent().unwrap().parent().unwrap().parent().unwrap().join(file_path);
 
     // For development, assumes exe is in target/{debug/release}
     if possible_path.exists() {
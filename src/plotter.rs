@@ -1,22 +1,56 @@
 
 use std::time::Instant;
 use std::path::Path;
-use log::{debug, info};
+use log::{debug, info, warn};
 use plotters::prelude::*;
+use plotters::chart::{DualCoordChartContext, MeshStyle, SeriesAnno};
+use plotters::drawing::backend::BackendCoord;
+use plotters::element::ComposedElement;
+use plotters::style::text_anchor::{Pos, HPos, VPos};
 use rayon::prelude::*;
+use statrs::statistics::OrderStatistics;
+use chrono::{Duration, NaiveDateTime};
 use std::str::FromStr;
-use std::num::ParseIntError;
 use std::io::{self, BufRead};
 use std::fs::File;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
 
 use crate::parameters::Parameters;
 use crate::data_loader::{LoadedData, FileData};
 use crate::FileName;
-use crate::parameters::TimeFormat;
+use crate::parameters::{TimeFormat, CorrelationMethod, ImageFormat};
+use crate::statistics::Statistics;
+use crate::correlation::FileCorrelations;
+use crate::metric_options::{AxisLimit, AxisScale, MetricOptions, MetricOptionsList};
+use crate::charts::{Chart, ChartLayout, ChartMetric};
+use crate::downsampler::downsample;
+use crate::formatting::{format_number, format_unit};
+use crate::annotations::{Annotations, Event, EventTime, ReferenceLine};
+use crate::history::{HistoryMetric, HistoryPoint, HistoryRun};
+
+// Runs the drawing code once for every requested image format, with `root` as the drawing area
+// of the image in that format
+macro_rules! draw_image {
+    ($param:expr, $image_name:expr, $size:expr, |$root:ident| $body:block) => {{
+        let base_path = Path::new(&$param.target_directory);
+        if let ImageFormat::Png | ImageFormat::Both = $param.image_format {
+            let image_path = base_path.join(format!("{}.png", $image_name));
+            let $root = BitMapBackend::new(&image_path, $size).into_drawing_area();
+            $body;
+        }
+        if let ImageFormat::Svg | ImageFormat::Both = $param.image_format {
+            let image_path = base_path.join(format!("{}.svg", $image_name));
+            let $root = SVGBackend::new(&image_path, $size).into_drawing_area();
+            $body;
+        }
+    }};
+}
 
 
-pub fn generate_plots(loaded_data: &LoadedData, param: &Parameters) -> Result<()> {
+pub fn generate_plots(loaded_data: &LoadedData, statistics: &Statistics, metric_options: &MetricOptionsList, annotations: &Annotations,
+        charts: &[Chart], param: &Parameters) -> Result<()> {
     let start = Instant::now();
     info!("Generating plots..");
 
@@ -27,14 +61,36 @@ pub fn generate_plots(loaded_data: &LoadedData, param: &Parameters) -> Result<()
 
         let mut files_that_contain_metric = Vec::new();
         for file_data in loaded_data.get_all_data() {
-            if file_data.contains_metric(&metric) {
+            if file_data.contains_metric(metric) {
                 files_that_contain_metric.push(file_data)
             }
         }
 
-        match create_plot(files_that_contain_metric, metric.clone(), param, &plot_settings) {
-            Err(e) => panic!("{:?}", e),
-            Ok(_) => (),
+        let options = metric_options.get(metric);
+        let unit = options.unit(metric);
+        if let Err(e) = create_plot(&files_that_contain_metric, metric.clone(), statistics, &options, annotations, param, &plot_settings) {
+            panic!("{:?}", e)
+        }
+        if let Err(e) = create_histogram(&files_that_contain_metric, metric, &unit, param, &plot_settings) {
+            panic!("{:?}", e)
+        }
+        if let Err(e) = create_cdf(&files_that_contain_metric, metric, &unit, param, &plot_settings) {
+            panic!("{:?}", e)
+        }
+        if let Err(e) = create_boxplot(&files_that_contain_metric, metric, &unit, statistics, param, &plot_settings) {
+            panic!("{:?}", e)
+        }
+    });
+
+    charts.par_iter().for_each(|chart| {
+        if let Err(e) = create_chart(loaded_data, chart, metric_options, param, &plot_settings) {
+            panic!("{:?}", e)
+        }
+    });
+
+    statistics.get_correlations().par_iter().for_each(|correlations| {
+        if let Err(e) = create_heatmap(correlations, param, &plot_settings) {
+            panic!("{:?}", e)
         }
     });
 
@@ -44,95 +100,649 @@ pub fn generate_plots(loaded_data: &LoadedData, param: &Parameters) -> Result<()
     Ok(())
 }
 
-fn create_plot(file_datas: Vec<&FileData>, metric: String, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
+fn create_plot(file_datas: &[&FileData], metric: String, statistics: &Statistics, options: &MetricOptions, annotations: &Annotations,
+        param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
     debug!("Creating plot for {}", metric);
 
-    // Image filename
-    let base_path = Path::new(&param.target_directory);
-    let image_path = base_path.join(metric.get_file_name(".png"));
-
-    // Get longest duration - X axis
-    let mut max_timestamp: usize = 0;
-    for file_data in &file_datas {
-        let initial_time = *file_data.timestamps.data.first().unwrap();
-        let last_time = *file_data.timestamps.data.last().unwrap();
-        let duration = last_time.signed_duration_since(initial_time);
-        let timestamp_value  = match &param.x_axis {
-            TimeFormat::Seconds => duration.num_seconds(),
-            TimeFormat::Minutes => duration.num_minutes(),
-        };
-        if timestamp_value as usize > max_timestamp {
-            max_timestamp = timestamp_value as usize;
-        }
-    } 
+    // Image filename, without the extension
+    let image_name = metric.get_file_name("");
 
-    // Get highest value -  Y Axis
-    let mut max_value: f64 = 0.0;
-    let mut max_amount_values: u32 = 0;
-    for file_data in &file_datas {
-        let metric_data = &file_data.metrics[&metric].data;
-        let current_max = metric_data.iter().cloned().fold(-1./0. /* -inf */, f64::max);
-        if current_max > max_value {
-            max_value = current_max;
-        }
+    // X axis, up to the end of the longest file
+    let x_axis = XAxis::new(file_datas, &param.x_axis);
 
-        let current_amount_max: u32 = metric_data.len() as u32;
+    // Y Axis, from the metric options, showing the reference lines of the metric
+    let reference_lines = annotations.get_lines(&metric);
+    let reference_values: Vec<f64> = reference_lines.iter().map(|line| line.value).collect();
+    let y_axis = YAxis::new(file_datas, &metric, options, &reference_values);
+    let unit = options.unit(&metric);
+    let mut max_amount_values: u32 = 0;
+    for file_data in file_datas {
+        let current_amount_max: u32 = file_data.metrics[&metric].data.len() as u32;
         if current_amount_max > max_amount_values {
             max_amount_values = current_amount_max;
         }
     }
 
     // Create base chart based on stats from each file
-    //let root = SVGBackend::new(&image_path, (max_amount_values * param.width_per_point, 768)).into_drawing_area();
-    let width = std::cmp::max(plot_settings.minimum_width, max_amount_values * param.width_per_point);
-    let root = BitMapBackend::new(&image_path, (width, 768)).into_drawing_area();
-    root.fill(&WHITE).with_context(|| "Filling plot color problems (weird...)")?;
-
-    // TODO: CALCULAR VALORES
-
-    let mut chart = ChartBuilder::on(&root)
-        .x_label_area_size(plot_settings.x_label_area_size)
-        .y_label_area_size(plot_settings.y_label_area_size)
-        .caption(&metric, ("sans-serif", plot_settings.caption_size).into_font()) // Size of caption
-        .build_ranged(0..max_timestamp, 0f64..max_value).with_context(|| "Building plot problems (weird...)")?;
-
-    chart.configure_mesh()
-        .x_desc("Time (seconds)")
-        .y_desc("Value")
+    let width = plot_width(max_amount_values, param, plot_settings);
+    draw_image!(param, image_name, (width, plot_settings.height), |root| {
+        root.fill(&plot_settings.background_colour()).with_context(|| "Filling plot color problems (weird...)")?;
+
+        let mut chart_builder = ChartBuilder::on(&root);
+        chart_builder
+            .margin(plot_settings.margin)
+            .x_label_area_size(plot_settings.x_label_area_size)
+            .y_label_area_size(plot_settings.y_label_area_size)
+            .caption(&metric, (plot_settings.font_family.as_str(), plot_settings.caption_size).into_font()); // Size of caption
+
+        // Both scales share the drawing code, only the coordinates differ
+        match options.y_scale {
+            AxisScale::Linear => {
+                let mut chart = chart_builder.build_ranged(0f64..x_axis.end, y_axis.lower..y_axis.upper)
+                    .with_context(|| "Building plot problems (weird...)")?;
+                draw_metric_lines(&mut chart, file_datas, &metric, &unit, &x_axis, &y_axis, statistics, param, plot_settings)?;
+                draw_annotations(&mut chart, file_datas, &reference_lines, annotations.get_events(), &unit, &x_axis, &y_axis, param, plot_settings)?;
+                draw_legend(&mut chart, plot_settings, SeriesLabelPosition::MiddleRight)?;
+            }
+            AxisScale::Log => {
+                let mut chart = chart_builder.build_ranged(0f64..x_axis.end, LogRange(y_axis.lower..y_axis.upper))
+                    .with_context(|| "Building plot problems (weird...)")?;
+                draw_metric_lines(&mut chart, file_datas, &metric, &unit, &x_axis, &y_axis, statistics, param, plot_settings)?;
+                draw_annotations(&mut chart, file_datas, &reference_lines, annotations.get_events(), &unit, &x_axis, &y_axis, param, plot_settings)?;
+                draw_legend(&mut chart, plot_settings, SeriesLabelPosition::MiddleRight)?;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+// Lines, anomalies, segment means and trends of every file on the metric chart
+#[allow(clippy::too_many_arguments)]
+fn draw_metric_lines<'a, DB, X, Y>(chart: &mut ChartContext<'a, DB, RangedCoord<X, Y>>, file_datas: &[&FileData], metric: &str, unit: &str, x_axis: &XAxis, y_axis: &YAxis,
+        statistics: &Statistics, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()>
+    where DB: DrawingBackend + 'a, DB::ErrorType: 'static, X: Ranged<ValueType = f64>, Y: Ranged<ValueType = f64> {
+
+    let x_label_formatter = |x: &f64| x_axis.label(*x, &param.x_axis, plot_settings.x_labels);
+    let y_label_formatter = |y: &f64| format_unit(*y, unit);
+    style_grid(&mut chart.configure_mesh(), plot_settings)
+        .x_desc(plot_settings.x_axis_label(&param.x_axis))
+        .x_label_formatter(&x_label_formatter)
+        .y_desc(plot_settings.y_axis_label.as_str())
+        .y_label_formatter(&y_label_formatter)
         .x_labels(plot_settings.x_labels) // Number of metrics on X axis
-        .x_label_style(("sans-serif", plot_settings.x_label_style).into_font())
+        .x_label_style((plot_settings.font_family.as_str(), plot_settings.x_label_style).into_font())
         .y_labels(plot_settings.y_labels) // Number of metrics on Y axis
-        .y_label_style(("sans-serif", plot_settings.y_label_style).into_font())
-       // .line_style_2(&WHITE)
+        .y_label_style((plot_settings.font_family.as_str(), plot_settings.y_label_style).into_font())
         .draw().with_context(|| "Drawing plot problems (weird...)")?;
 
-    let colors = get_colors(param, &plot_settings);
+    let colors = get_colors(param, plot_settings, file_datas.len());
+    let pixels = chart.plotting_area().dim_in_pixel().0 as usize;
+
+    // Anomalies are shaded behind the lines
+    for (idx, file_data) in file_datas.iter().enumerate() {
+        let anomalies = &statistics.get_stat(metric, &file_data.file_name).unwrap().anomalies;
+        if anomalies.is_empty() {
+            continue;
+        }
+
+        // Every flagged sample is shaded up to where the next one would be
+        let offset = x_axis.file_offset(file_data, &param.x_axis);
+        let elapsed_seconds = file_data.get_elapsed_seconds();
+        let sample_interval = match elapsed_seconds.len() {
+            0 | 1 => 1.0,
+            amount => elapsed_seconds[amount - 1] / (amount - 1) as f64
+        };
+
+        let anomaly_colour = ShapeStyle { color: colors[idx].style.color.mix(0.2), filled: true, stroke_width: 0 };
+        chart.draw_series(anomalies.iter().map(|anomaly| {
+            let start = seconds_to_x(offset + anomaly.start, &param.x_axis);
+            let end = seconds_to_x(offset + anomaly.end + sample_interval, &param.x_axis);
+            Rectangle::new([(start, y_axis.lower), (end, y_axis.upper)], anomaly_colour.clone())
+        })).with_context(|| "Anomaly drawing problems (weird...)")?
+            .label(format!("{} anomalies", file_data.file_name))
+            .legend(move |(x, y)| 
+                Rectangle::new([(x, y - 5), (x + 20, y + 5)], anomaly_colour.clone()));
+    }
 
     for (idx, file_data) in file_datas.iter().enumerate() {
-        let metric_data = &file_data.metrics[&metric].data;
-        let timestamps = &file_data.timestamps.data;
+        let metric_data = &file_data.metrics[metric].data;
+        let elapsed_seconds = file_data.get_elapsed_seconds();
+        let offset = x_axis.file_offset(file_data, &param.x_axis);
         let file_name = &file_data.file_name;
-        let first_timestamp = timestamps.first().unwrap();
         let colour = colors[idx].clone();
+        let trend_colour = ShapeStyle { color: colour.style.color.mix(0.6), filled: true, stroke_width: plot_settings.stroke_width };
+        let segment_colour = ShapeStyle { color: colour.style.color.mix(0.5), filled: true, stroke_width: plot_settings.stroke_width + 2 };
 
+        let points: Vec<(f64, f64)> = elapsed_seconds.iter().zip(metric_data)
+            .map(|(second, value)| (seconds_to_x(offset + second, &param.x_axis), *value))
+            .collect();
 
-        chart.draw_series(LineSeries::new(
-            timestamps.iter().zip(metric_data).map(|(time, value)| {
-                let duration = time.signed_duration_since(*first_timestamp);
-                let duration_value  = match &param.x_axis {
-                    TimeFormat::Seconds => duration.num_seconds(),
-                    TimeFormat::Minutes => duration.num_minutes(),
-                };
-                (duration_value as usize, *value) 
-            }), colour.clone())).with_context(|| "Plot line drawing problems (weird...)")?
+        let shown_points = points.iter().map(|(x, value)| (*x, y_axis.shown_value(*value))).collect();
+        draw_file_line(chart, downsample(shown_points, pixels, param), &colour)?
             .label(file_name)
+            .legend(move |position| legend_line(position, &colour));
+
+        // Samples above the clip are marked where they leave the chart
+        if let Some(clip) = y_axis.clip {
+            let clipped: Vec<(f64, f64)> = points.iter().filter(|(_, value)| *value > clip).map(|(x, _)| (*x, clip)).collect();
+            if !clipped.is_empty() {
+                let clip_colour = colors[idx].style.clone();
+                chart.draw_series(clipped.into_iter().map(|point| TriangleMarker::new(point, 6, clip_colour.clone())))
+                    .with_context(|| "Clipped values drawing problems (weird...)")?
+                    .label(format!("{} values above {:.2}", file_name, clip))
+                    .legend(move |(x, y)| 
+                        TriangleMarker::new((x + 10, y), 6, clip_colour.clone()));
+            }
+        }
+
+        let stat = statistics.get_stat(metric, file_name).unwrap();
+
+        // Mean of each segment between change points, as steps
+        if stat.segments.len() > 1 {
+            let segment_points: Vec<(f64, f64)> = stat.segments.iter()
+                .flat_map(|segment| vec![
+                    (seconds_to_x(offset + segment.start, &param.x_axis), y_axis.shown_value(segment.mean)),
+                    (seconds_to_x(offset + segment.end, &param.x_axis), y_axis.shown_value(segment.mean))
+                ])
+                .collect();
+
+            chart.draw_series(LineSeries::new(segment_points, segment_colour.clone()))
+                .with_context(|| "Segment drawing problems (weird...)")?
+                .label(format!("{} segment means", file_name))
+                .legend(move |(x, y)| 
+                    PathElement::new(vec![(x, y), (x + 20, y)], segment_colour.clone()));
+        }
+
+        // Trend line, from the first to the last sample of the file
+        let trend = &stat.trend;
+        let draw_trend = match plot_settings.trend_lines {
+            TrendLines::All => true,
+            TrendLines::Significant => trend.significant,
+            TrendLines::None => false
+        };
+        if draw_trend {
+            let last_second = *elapsed_seconds.last().unwrap();
+            let trend_points: Vec<(f64, f64)> = vec![0.0, last_second].into_iter()
+                .map(|second| (seconds_to_x(offset + second, &param.x_axis), y_axis.shown_value(trend.intercept + trend.slope * second)))
+                .collect();
+
+            chart.draw_series(LineSeries::new(trend_points, trend_colour.clone()))
+                .with_context(|| "Trend line drawing problems (weird...)")?
+                .label(format!("{} trend ({}{}/hour)", file_name, if trend.growth_per_hour > 0.0 { "+" } else { "" }, format_unit(trend.growth_per_hour, unit)))
+                .legend(move |(x, y)| 
+                    PathElement::new(vec![(x, y), (x + 20, y)], trend_colour.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+// Dashed reference lines, and events as vertical lines with their label on top
+#[allow(clippy::too_many_arguments)]
+fn draw_annotations<'a, DB, X, Y>(chart: &mut ChartContext<'a, DB, RangedCoord<X, Y>>, file_datas: &[&FileData], reference_lines: &[&ReferenceLine], events: &[Event], unit: &str,
+        x_axis: &XAxis, y_axis: &YAxis, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()>
+    where DB: DrawingBackend + 'a, DB::ErrorType: 'static, X: Ranged<ValueType = f64>, Y: Ranged<ValueType = f64> {
+
+    for reference_line in reference_lines {
+        // Lines out of a fixed Y axis are left out
+        if reference_line.value < y_axis.lower || reference_line.value > y_axis.upper {
+            continue;
+        }
+
+        let rgb = &reference_line.colour;
+        let line_style = FileStyle {
+            style: ShapeStyle { color: RGBColor(rgb.0, rgb.1, rgb.2).to_rgba(), filled: true, stroke_width: plot_settings.stroke_width },
+            rgb: (rgb.0, rgb.1, rgb.2),
+            dash: Some((8, 4))
+        };
+        draw_file_line(chart, vec![(0.0, reference_line.value), (x_axis.end, reference_line.value)], &line_style)?
+            .label(format!("{} ({})", reference_line.label, format_unit(reference_line.value, unit)))
+            .legend(move |position| legend_line(position, &line_style));
+    }
+
+    let base_pixel = chart.plotting_area().get_base_pixel();
+    let label_style = TextStyle::from((plot_settings.font_family.as_str(), plot_settings.legend_label_font).into_font());
+    for event in events {
+        // Timestamps fall on each file that covers them, at their place in that file
+        let mut positions: Vec<f64> = Vec::new();
+        for file_data in file_datas {
+            let elapsed_seconds = file_data.get_elapsed_seconds();
+            let second = match &event.time {
+                EventTime::Elapsed(second) => *second,
+                EventTime::At(time) => {
+                    let first_timestamp = *file_data.timestamps.data.first().unwrap();
+                    time.signed_duration_since(first_timestamp).num_microseconds().unwrap_or(0) as f64 / 1_000_000.0
+                }
+            };
+            if second < 0.0 || second > *elapsed_seconds.last().unwrap() {
+                continue;
+            }
+
+            let x = seconds_to_x(x_axis.file_offset(file_data, &param.x_axis) + second, &param.x_axis);
+            if !positions.iter().any(|position| (position - x).abs() < 1e-6) {
+                positions.push(x);
+            }
+        }
+        if positions.is_empty() {
+            continue;
+        }
+
+        let event_colour = ShapeStyle { color: RGBColor(event.colour.0, event.colour.1, event.colour.2).to_rgba(), filled: true, stroke_width: plot_settings.stroke_width };
+        chart.draw_series(positions.iter().map(|x| PathElement::new(vec![(*x, y_axis.lower), (*x, y_axis.upper)], event_colour.clone())))
+            .with_context(|| "Event drawing problems (weird...)")?
+            .label(&event.label)
             .legend(move |(x, y)| 
-                (PathElement::new(vec![(x, y), (x + 20, y)], colour.clone())));
+                PathElement::new(vec![(x + 10, y - 8), (x + 10, y + 8)], event_colour.clone()));
+
+        let text_area = chart.plotting_area().strip_coord_spec();
+        for x in &positions {
+            let (pixel_x, _) = chart.backend_coord(&(*x, y_axis.upper));
+            text_area.draw(&Text::new(event.label.clone(), (pixel_x - base_pixel.0 + 4, 4), label_style.color(&RGBColor(event.colour.0, event.colour.1, event.colour.2))))
+                .with_context(|| "Event label drawing problems (weird...)")?;
+        }
+    }
+
+    Ok(())
+}
+
+// Time range of the metric chart
+struct XAxis {
+    // Earliest first sample of the files, where clock axes start
+    start: NaiveDateTime,
+    // In the unit of the axis
+    end: f64
+}
+
+impl XAxis {
+
+    fn new(file_datas: &[&FileData], x_axis: &TimeFormat) -> XAxis {
+        let start = file_datas.iter()
+            .map(|file_data| *file_data.timestamps.data.first().unwrap())
+            .min()
+            .unwrap();
+
+        let mut x_axis_range = XAxis { start, end: 0.0 };
+        for file_data in file_datas {
+            let duration = *file_data.get_elapsed_seconds().last().unwrap();
+            let file_end = seconds_to_x(x_axis_range.file_offset(file_data, x_axis) + duration, x_axis);
+            x_axis_range.end = x_axis_range.end.max(file_end);
+        }
+        if x_axis_range.end <= 0.0 {
+            x_axis_range.end = 1.0;
+        }
+
+        x_axis_range
+    }
+
+    // Seconds from the start of the axis to the first sample of the file. Only clock axes
+    // keep files apart, the others start every file at 0.
+    fn file_offset(&self, file_data: &FileData, x_axis: &TimeFormat) -> f64 {
+        match x_axis {
+            TimeFormat::Clock => {
+                let first_timestamp = *file_data.timestamps.data.first().unwrap();
+                first_timestamp.signed_duration_since(self.start).num_microseconds().unwrap_or(0) as f64 / 1_000_000.0
+            }
+            _ => 0.0
+        }
+    }
+
+    fn label(&self, x: f64, x_axis: &TimeFormat, labels: usize) -> String {
+        match x_axis {
+            TimeFormat::Clock => {
+                let time = self.start + Duration::microseconds((x * 1_000_000.0) as i64);
+                let label_format = if self.end >= 24.0 * 3600.0 {
+                    "%m/%d %H:%M"
+                } else if self.end < 10.0 {
+                    "%H:%M:%S%.3f"
+                } else {
+                    "%H:%M:%S"
+                };
+                time.format(label_format).to_string()
+            }
+            _ => {
+                // Ticks are never closer than the axis split in the amount of labels, which gives
+                // the decimals they need
+                let step = self.end / labels.max(1) as f64;
+                let decimals = (-step.log10().floor()).max(0.0) as usize;
+                let text = format_number(x, decimals);
+                if text.contains('.') { text.trim_end_matches('0').trim_end_matches('.').to_string() } else { text }
+            }
+        }
+    }
+}
+
+// Value range of the metric chart, following the axis options of the metric
+struct YAxis {
+    lower: f64,
+    upper: f64,
+    // Values above it are drawn on it
+    clip: Option<f64>
+}
+
+impl YAxis {
+
+    // Automatic limits also make room for the reference values
+    fn new(file_datas: &[&FileData], metric: &str, options: &MetricOptions, reference_values: &[f64]) -> YAxis {
+        let (min_value, max_value) = value_range(file_datas, metric);
+        let clip = options.y_clip_percentile
+            .map(|percentile| {
+                let mut values: Vec<f64> = file_datas.iter()
+                    .flat_map(|file_data| file_data.metrics[metric].data.iter().cloned())
+                    .collect();
+                values.quantile(percentile / 100.0)
+            })
+            .filter(|clip| *clip < max_value);
+
+        let mut lower = match options.y_min {
+            AxisLimit::Auto => reference_values.iter().cloned().fold(min_value, f64::min),
+            AxisLimit::Fixed(value) => value
+        };
+        let mut upper = match options.y_max {
+            AxisLimit::Auto => reference_values.iter().cloned().fold(clip.unwrap_or(max_value), f64::max),
+            AxisLimit::Fixed(value) => value
+        };
+
+        let log = matches!(options.y_scale, AxisScale::Log);
+        if log && lower <= 0.0 {
+            // Logarithmic axes start at the lowest positive value instead
+            lower = file_datas.iter()
+                .flat_map(|file_data| file_data.metrics[metric].data.iter().cloned())
+                .filter(|value| *value > 0.0)
+                .fold(f64::INFINITY, f64::min);
+            if !lower.is_finite() {
+                lower = 1.0;
+            }
+        }
+        if upper <= lower {
+            upper = if log { lower * 10.0 } else { lower + 1.0 };
+        }
+
+        YAxis {
+            lower,
+            upper,
+            clip: clip.map(|clip| clip.min(upper)).filter(|clip| *clip > lower)
+        }
     }
 
+    // Value where a sample is drawn. Clipped samples stay on the top and,
+    // since logarithmic axes start above zero, low samples stay on the bottom.
+    fn shown_value(&self, value: f64) -> f64 {
+        let value = match self.clip {
+            Some(clip) => value.min(clip),
+            None => value
+        };
+
+        value.max(self.lower)
+    }
+}
+
+
+fn create_chart(loaded_data: &LoadedData, chart: &Chart, metric_options: &MetricOptionsList, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
+    debug!("Creating chart {}", chart.title);
+
+    match chart.layout {
+        ChartLayout::Overlay => create_overlay_chart(loaded_data, chart, metric_options, param, plot_settings),
+        ChartLayout::Grid => create_grid_chart(loaded_data, chart, metric_options, param, plot_settings)
+    }
+}
+
+// A line per metric and file, the secondary metrics against the right Y axis
+fn create_overlay_chart(loaded_data: &LoadedData, chart: &Chart, metric_options: &MetricOptionsList, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
+    let file_datas = chart_files(loaded_data, chart);
+    let unit = shared_unit(&chart.metrics, metric_options);
+    let secondary_unit = shared_unit(&chart.secondary_metrics, metric_options);
+    let x_axis = XAxis::new(&file_datas, &param.x_axis);
+    let (lower, upper) = chart_value_range(&file_datas, &chart.metrics);
+    let (secondary_lower, secondary_upper) = chart_value_range(&file_datas, &chart.secondary_metrics);
+
+    // Metric, file and whether the metric is drawn against the right Y axis
+    let mut series: Vec<(&str, &FileData, bool)> = Vec::new();
+    for (metrics, secondary) in [(&chart.metrics, false), (&chart.secondary_metrics, true)] {
+        for metric in metrics {
+            for file_data in file_datas.iter().filter(|file_data| file_data.contains_metric(&metric.name)) {
+                series.push((&metric.name, file_data, secondary));
+            }
+        }
+    }
+    let colors = get_colors(param, plot_settings, series.len());
+
+    let max_amount_values = series.iter()
+        .map(|(metric, file_data, _)| file_data.metrics[*metric].data.len() as u32)
+        .max()
+        .unwrap_or(0);
+    let width = plot_width(max_amount_values, param, plot_settings);
+    draw_image!(param, chart.image_name, (width, plot_settings.height), |root| {
+        root.fill(&plot_settings.background_colour()).with_context(|| "Filling plot color problems (weird...)")?;
+
+        let mut chart_builder = ChartBuilder::on(&root);
+        chart_builder
+            .margin(plot_settings.margin)
+            .x_label_area_size(plot_settings.x_label_area_size)
+            .y_label_area_size(plot_settings.y_label_area_size)
+            .caption(&chart.title, (plot_settings.font_family.as_str(), plot_settings.caption_size).into_font());
+        if !chart.secondary_metrics.is_empty() {
+            chart_builder.right_y_label_area_size(plot_settings.y_label_area_size);
+        }
+        // The right axis only has labels when there are secondary metrics
+        let mut chart_context = chart_builder.build_ranged(0f64..x_axis.end, lower..upper)
+            .with_context(|| "Building plot problems (weird...)")?
+            .set_secondary_coord(0f64..x_axis.end, secondary_lower..secondary_upper);
+
+        let x_label_formatter = |x: &f64| x_axis.label(*x, &param.x_axis, plot_settings.x_labels);
+        let y_label_formatter = |y: &f64| format_unit(*y, &unit);
+        let secondary_label_formatter = |y: &f64| format_unit(*y, &secondary_unit);
+        style_grid(&mut chart_context.configure_mesh(), plot_settings)
+            .x_desc(plot_settings.x_axis_label(&param.x_axis))
+            .x_label_formatter(&x_label_formatter)
+            .y_desc(plot_settings.y_axis_label.as_str())
+            .y_label_formatter(&y_label_formatter)
+            .x_labels(plot_settings.x_labels)
+            .x_label_style((plot_settings.font_family.as_str(), plot_settings.x_label_style).into_font())
+            .y_labels(plot_settings.y_labels)
+            .y_label_style((plot_settings.font_family.as_str(), plot_settings.y_label_style).into_font())
+            .draw().with_context(|| "Drawing plot problems (weird...)")?;
+        if !chart.secondary_metrics.is_empty() {
+            chart_context.configure_secondary_axes()
+                .y_desc(format!("{} (right axis)", plot_settings.y_axis_label))
+                .y_label_formatter(&secondary_label_formatter)
+                .y_labels(plot_settings.y_labels)
+                .label_style((plot_settings.font_family.as_str(), plot_settings.y_label_style).into_font())
+                .draw().with_context(|| "Drawing plot problems (weird...)")?;
+        }
+
+        let pixels = chart_context.plotting_area().dim_in_pixel().0 as usize;
+        for ((metric, file_data, secondary), colour) in series.iter().zip(&colors) {
+            let colour = colour.clone();
+            let points = downsample(metric_points(file_data, metric, &x_axis, param), pixels, param);
+            let label = series_label(metric, file_data, file_datas.len());
+            let series_anno = if *secondary {
+                draw_secondary_file_line(&mut chart_context, points, &colour)?.label(format!("{} (right axis)", label))
+            } else {
+                draw_file_line(&mut *chart_context, points, &colour)?.label(label)
+            };
+            series_anno.legend(move |position| legend_line(position, &colour));
+        }
+
+        draw_legend(&mut *chart_context, plot_settings, SeriesLabelPosition::UpperRight)?;
+    });
+
+    Ok(())
+}
+
+// A subplot per metric with a line per file, sharing the X axis so the subplots line up
+fn create_grid_chart(loaded_data: &LoadedData, chart: &Chart, metric_options: &MetricOptionsList, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
+    let file_datas = chart_files(loaded_data, chart);
+    let x_axis = XAxis::new(&file_datas, &param.x_axis);
+    let colors = get_colors(param, plot_settings, file_datas.len());
+
+    let columns = chart.columns.min(chart.metrics.len());
+    let rows = chart.metrics.len().div_ceil(columns);
+    let height = plot_settings.caption_size + rows as u32 * plot_settings.height / 2;
+    draw_image!(param, chart.image_name, (plot_settings.minimum_width, height), |root| {
+        root.fill(&plot_settings.background_colour()).with_context(|| "Filling plot color problems (weird...)")?;
+        let grid_area = root.titled(&chart.title, (plot_settings.font_family.as_str(), plot_settings.caption_size).into_font())
+            .with_context(|| "Building plot problems (weird...)")?;
+
+        for (idx, (metric, cell)) in chart.metrics.iter().zip(grid_area.split_evenly((rows, columns))).enumerate() {
+            let (lower, upper) = chart_value_range(&file_datas, std::slice::from_ref(metric));
+            let mut chart_context = ChartBuilder::on(&cell)
+                .margin(plot_settings.margin)
+                .x_label_area_size(plot_settings.x_label_area_size)
+                .y_label_area_size(plot_settings.y_label_area_size)
+                .caption(&metric.name, (plot_settings.font_family.as_str(), plot_settings.caption_size / 2).into_font())
+                .build_ranged(0f64..x_axis.end, lower..upper).with_context(|| "Building plot problems (weird...)")?;
+
+            let unit = metric_options.get(&metric.name).unit(&metric.name);
+            let x_label_formatter = |x: &f64| x_axis.label(*x, &param.x_axis, plot_settings.x_labels);
+            let y_label_formatter = |y: &f64| format_unit(*y, &unit);
+            style_grid(&mut chart_context.configure_mesh(), plot_settings)
+                .x_desc(plot_settings.x_axis_label(&param.x_axis))
+                .x_label_formatter(&x_label_formatter)
+                .y_label_formatter(&y_label_formatter)
+                .x_labels(plot_settings.x_labels)
+                .x_label_style((plot_settings.font_family.as_str(), plot_settings.x_label_style).into_font())
+                .y_labels(plot_settings.y_labels)
+                .y_label_style((plot_settings.font_family.as_str(), plot_settings.y_label_style).into_font())
+                .draw().with_context(|| "Drawing plot problems (weird...)")?;
+
+            let pixels = chart_context.plotting_area().dim_in_pixel().0 as usize;
+            for (file_data, colour) in file_datas.iter().zip(&colors) {
+                if !file_data.contains_metric(&metric.name) {
+                    continue;
+                }
+                let colour = colour.clone();
+                let points = downsample(metric_points(file_data, &metric.name, &x_axis, param), pixels, param);
+                draw_file_line(&mut chart_context, points, &colour)?
+                    .label(&file_data.file_name)
+                    .legend(move |position| legend_line(position, &colour));
+            }
+
+            // Files keep their colour in every subplot, one legend is enough
+            if idx == 0 {
+                draw_legend(&mut chart_context, plot_settings, SeriesLabelPosition::UpperRight)?;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+// Plots keep the minimum width and downsample their lines, unless they get a width per point
+fn plot_width(max_amount_values: u32, param: &Parameters, plot_settings: &PlotterSettings) -> u32 {
+    match param.width_per_point {
+        Some(width_per_point) => std::cmp::max(plot_settings.minimum_width, max_amount_values * width_per_point),
+        None => plot_settings.minimum_width
+    }
+}
+
+// Files that have any of the metrics of the chart
+fn chart_files<'d>(loaded_data: &'d LoadedData, chart: &Chart) -> Vec<&'d FileData> {
+    loaded_data.get_all_data().iter()
+        .filter(|file_data| chart.metrics.iter().chain(&chart.secondary_metrics)
+            .any(|metric| file_data.contains_metric(&metric.name)))
+        .collect()
+}
+
+// The unit of the metrics when they all share it, no unit otherwise
+fn shared_unit(metrics: &[ChartMetric], metric_options: &MetricOptionsList) -> String {
+    let mut units = metrics.iter().map(|metric| metric_options.get(&metric.name).unit(&metric.name));
+    let first = units.next().unwrap_or_default();
+    if units.all(|unit| unit == first) { first } else { String::new() }
+}
+
+// Value range shared by the metrics, starting at zero like the metric charts
+fn chart_value_range(file_datas: &[&FileData], metrics: &[ChartMetric]) -> (f64, f64) {
+    let mut lower: f64 = 0.0;
+    let mut upper: f64 = 0.0;
+    for metric in metrics {
+        let metric_files: Vec<&FileData> = file_datas.iter()
+            .filter(|file_data| file_data.contains_metric(&metric.name))
+            .cloned()
+            .collect();
+        let (min_value, max_value) = value_range(&metric_files, &metric.name);
+        lower = lower.min(min_value);
+        upper = upper.max(max_value);
+    }
+
+    if upper <= lower {
+        (lower, lower + 1.0)
+    } else {
+        (lower, upper)
+    }
+}
+
+fn metric_points(file_data: &FileData, metric: &str, x_axis: &XAxis, param: &Parameters) -> Vec<(f64, f64)> {
+    let offset = x_axis.file_offset(file_data, &param.x_axis);
+    file_data.get_elapsed_seconds().iter().zip(&file_data.metrics[metric].data)
+        .map(|(second, value)| (seconds_to_x(offset + second, &param.x_axis), *value))
+        .collect()
+}
+
+// Lines of a metric plot, for the charts drawn in the browser
+#[derive(Serialize)]
+pub struct InteractiveSeries {
+    pub name: String,
+    pub x_label: String,
+    pub unit: String,
+    // Milliseconds since the epoch where clock axes start, X values are seconds from it
+    pub clock_start: Option<i64>,
+    pub lines: Vec<InteractiveLine>
+}
+
+#[derive(Serialize)]
+pub struct InteractiveLine {
+    pub name: String,
+    pub colour: String,
+    pub dash: Option<(u32, u32)>,
+    pub points: Vec<(f64, f64)>
+}
+
+// The lines of every metric plot, by metric key, downsampled to the plot width like the images
+pub fn interactive_series(loaded_data: &LoadedData, metric_options: &MetricOptionsList, param: &Parameters) -> HashMap<String, InteractiveSeries> {
+    let plot_settings = get_settings(0, 0, param);
+
+    loaded_data.get_distinct_metrics().par_iter().map(|metric| {
+        let file_datas = loaded_data.get_files_that_contain_metric(metric);
+        let x_axis = XAxis::new(&file_datas, &param.x_axis);
+        let colors = get_colors(param, &plot_settings, file_datas.len());
+
+        let lines = file_datas.iter().zip(colors).map(|(file_data, file_style)| InteractiveLine {
+            name: file_data.file_name.clone(),
+            colour: format!("#{:02x}{:02x}{:02x}", file_style.rgb.0, file_style.rgb.1, file_style.rgb.2),
+            dash: file_style.dash,
+            points: downsample(metric_points(file_data, metric, &x_axis, param), plot_settings.minimum_width as usize, param)
+        }).collect();
+
+        let series = InteractiveSeries {
+            name: metric.clone(),
+            x_label: plot_settings.x_axis_label(&param.x_axis),
+            unit: metric_options.get(metric).unit(metric),
+            clock_start: match param.x_axis {
+                TimeFormat::Clock => Some(x_axis.start.timestamp_millis()),
+                _ => None
+            },
+            lines
+        };
+        (metric.get_file_name(""), series)
+    }).collect()
+}
+
+// The file is only named when the chart compares several files
+fn series_label(metric: &str, file_data: &FileData, amount_files: usize) -> String {
+    match amount_files {
+        1 => metric.to_string(),
+        _ => format!("{} ({})", metric, file_data.file_name)
+    }
+}
+
+fn draw_legend<'a, DB, CT>(chart: &mut ChartContext<'a, DB, CT>, plot_settings: &PlotterSettings, default_position: SeriesLabelPosition) -> Result<()>
+    where DB: DrawingBackend + 'a, DB::ErrorType: 'static, CT: CoordTranslate {
+
     chart.configure_series_labels()
-        .label_font(("sans-serif", plot_settings.legend_label_font).into_font())
-        .background_style(&WHITE.mix(0.8))
+        .position(plot_settings.legend_position(default_position))
+        .label_font((plot_settings.font_family.as_str(), plot_settings.legend_label_font).into_font())
+        .background_style(&plot_settings.background_colour().mix(0.8))
         .border_style(&BLACK)
         .draw().with_context(|| "Final plot building step problems (weird...)")?;
 
@@ -140,53 +750,710 @@ fn create_plot(file_datas: Vec<&FileData>, metric: String, param: &Parameters, p
 }
 
 
+// Share of the samples of each file that falls in each bin, drawn as outlines so files can overlap
+fn create_histogram(file_datas: &[&FileData], metric: &str, unit: &str, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
+    debug!("Creating histogram for {}", metric);
+
+    let image_name = metric.to_string().get_file_name("_histogram");
+    let (min_value, max_value) = value_range(file_datas, metric);
+    let bins = plot_settings.histogram_bins.max(1);
+    let bin_width = if max_value > min_value { (max_value - min_value) / bins as f64 } else { 1.0 };
+
+    let mut histograms: Vec<Vec<f64>> = Vec::new();
+    for file_data in file_datas {
+        let metric_data = &file_data.metrics[metric].data;
+        let mut counts = vec![0.0; bins];
+        for value in metric_data {
+            let bin = (((value - min_value) / bin_width) as usize).min(bins - 1);
+            counts[bin] += 1.0;
+        }
+        histograms.push(counts.iter().map(|count| count * 100.0 / metric_data.len().max(1) as f64).collect());
+    }
+    let max_share = histograms.iter().flatten().cloned().fold(0.0, f64::max);
+
+    draw_image!(param, image_name, (plot_settings.distribution_width, plot_settings.distribution_height), |root| {
+        root.fill(&plot_settings.background_colour()).with_context(|| "Filling plot color problems (weird...)")?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(plot_settings.margin)
+            .x_label_area_size(plot_settings.x_label_area_size)
+            .y_label_area_size(plot_settings.y_label_area_size)
+            .caption("Histogram", (plot_settings.font_family.as_str(), plot_settings.caption_size).into_font())
+            .build_ranged(min_value..(min_value + bin_width * bins as f64), 0f64..(max_share * 1.05).max(1.0)).with_context(|| "Building plot problems (weird...)")?;
+
+        let value_label_formatter = |value: &f64| format_unit(*value, unit);
+        style_grid(&mut chart.configure_mesh(), plot_settings)
+            .x_desc(plot_settings.y_axis_label.as_str())
+            .x_label_formatter(&value_label_formatter)
+            .y_desc("% of samples")
+            .x_labels(plot_settings.x_labels)
+            .x_label_style((plot_settings.font_family.as_str(), plot_settings.x_label_style).into_font())
+            .y_labels(plot_settings.y_labels)
+            .y_label_style((plot_settings.font_family.as_str(), plot_settings.y_label_style).into_font())
+            .draw().with_context(|| "Drawing plot problems (weird...)")?;
+
+        let colors = get_colors(param, plot_settings, file_datas.len());
+        for (idx, (file_data, shares)) in file_datas.iter().zip(&histograms).enumerate() {
+            let colour = colors[idx].clone();
+
+            // Steps along the top of every bin
+            let mut outline = vec![(min_value, 0.0)];
+            for (bin, share) in shares.iter().enumerate() {
+                let bin_start = min_value + bin as f64 * bin_width;
+                outline.push((bin_start, *share));
+                outline.push((bin_start + bin_width, *share));
+            }
+            outline.push((min_value + bins as f64 * bin_width, 0.0));
+
+            draw_file_line(&mut chart, outline, &colour)?
+                .label(&file_data.file_name)
+                .legend(move |position| legend_line(position, &colour));
+        }
+
+        chart.configure_series_labels()
+            .position(plot_settings.legend_position(SeriesLabelPosition::MiddleRight))
+            .label_font((plot_settings.font_family.as_str(), plot_settings.legend_label_font).into_font())
+            .background_style(&plot_settings.background_colour().mix(0.8))
+            .border_style(&BLACK)
+            .draw().with_context(|| "Final plot building step problems (weird...)")?;
+    });
+
+    Ok(())
+}
+
+// Empirical cumulative distribution of each file
+fn create_cdf(file_datas: &[&FileData], metric: &str, unit: &str, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
+    debug!("Creating CDF for {}", metric);
+
+    let image_name = metric.to_string().get_file_name("_cdf");
+    let (min_value, max_value) = value_range(file_datas, metric);
+    let max_value = if max_value > min_value { max_value } else { min_value + 1.0 };
+
+    draw_image!(param, image_name, (plot_settings.distribution_width, plot_settings.distribution_height), |root| {
+        root.fill(&plot_settings.background_colour()).with_context(|| "Filling plot color problems (weird...)")?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(plot_settings.margin)
+            .x_label_area_size(plot_settings.x_label_area_size)
+            .y_label_area_size(plot_settings.y_label_area_size)
+            .caption("Cumulative distribution", (plot_settings.font_family.as_str(), plot_settings.caption_size).into_font())
+            .build_ranged(min_value..max_value, 0f64..100f64).with_context(|| "Building plot problems (weird...)")?;
 
-fn get_colors(param: &Parameters, plot_settings: &PlotterSettings) -> Vec<ShapeStyle> {
+        let value_label_formatter = |value: &f64| format_unit(*value, unit);
+        style_grid(&mut chart.configure_mesh(), plot_settings)
+            .x_desc(plot_settings.y_axis_label.as_str())
+            .x_label_formatter(&value_label_formatter)
+            .y_desc("% of samples below")
+            .x_labels(plot_settings.x_labels)
+            .x_label_style((plot_settings.font_family.as_str(), plot_settings.x_label_style).into_font())
+            .y_labels(plot_settings.y_labels)
+            .y_label_style((plot_settings.font_family.as_str(), plot_settings.y_label_style).into_font())
+            .draw().with_context(|| "Drawing plot problems (weird...)")?;
+
+        let colors = get_colors(param, plot_settings, file_datas.len());
+        for (idx, file_data) in file_datas.iter().enumerate() {
+            let colour = colors[idx].clone();
+            let mut sorted = file_data.metrics[metric].data.clone();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+            // At most one point per horizontal pixel is needed
+            let amount = sorted.len();
+            let step = std::cmp::max(1, amount / plot_settings.distribution_width as usize);
+            let points: Vec<(f64, f64)> = sorted.iter().enumerate()
+                .filter(|(position, _)| position % step == 0 || *position == amount - 1)
+                .map(|(position, value)| (*value, (position + 1) as f64 * 100.0 / amount as f64))
+                .collect();
+
+            draw_file_line(&mut chart, points, &colour)?
+                .label(&file_data.file_name)
+                .legend(move |position| legend_line(position, &colour));
+        }
+
+        chart.configure_series_labels()
+            .position(plot_settings.legend_position(SeriesLabelPosition::LowerRight))
+            .label_font((plot_settings.font_family.as_str(), plot_settings.legend_label_font).into_font())
+            .background_style(&plot_settings.background_colour().mix(0.8))
+            .border_style(&BLACK)
+            .draw().with_context(|| "Final plot building step problems (weird...)")?;
+    });
+
+    Ok(())
+}
+
+// One box per file: the box spans the 25th to the 75th percentile, the whiskers the 1st to the 99th,
+// with marks on the median and the 90th percentile
+fn create_boxplot(file_datas: &[&FileData], metric: &str, unit: &str, statistics: &Statistics, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
+    debug!("Creating box plot for {}", metric);
+
+    let image_name = metric.to_string().get_file_name("_boxplot");
+    let stats: Vec<_> = file_datas.iter()
+        .map(|file_data| statistics.get_stat(metric, &file_data.file_name).unwrap())
+        .collect();
+    let min_value = stats.iter().map(|stat| stat.p1st_percentile).fold(f64::INFINITY, f64::min);
+    let max_value = stats.iter().map(|stat| stat.p99th_percentile).fold(f64::NEG_INFINITY, f64::max);
+    let margin = if max_value > min_value { (max_value - min_value) * 0.05 } else { 1.0 };
+
+    let amount = file_datas.len();
+    let width = std::cmp::max(plot_settings.distribution_width, plot_settings.y_label_area_size + amount as u32 * 80);
+    draw_image!(param, image_name, (width, plot_settings.distribution_height), |root| {
+        root.fill(&plot_settings.background_colour()).with_context(|| "Filling plot color problems (weird...)")?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(plot_settings.margin)
+            .x_label_area_size(plot_settings.x_label_area_size)
+            .y_label_area_size(plot_settings.y_label_area_size)
+            .caption("Percentiles per file", (plot_settings.font_family.as_str(), plot_settings.caption_size).into_font())
+            .build_ranged(-0.5f64..(amount as f64 - 0.5), (min_value - margin)..(max_value + margin)).with_context(|| "Building plot problems (weird...)")?;
+
+        // File names are drawn under each box instead of numeric X labels
+        let value_label_formatter = |value: &f64| format_unit(*value, unit);
+        style_grid(&mut chart.configure_mesh(), plot_settings)
+            .disable_x_mesh()
+            .x_labels(0)
+            .y_desc(plot_settings.y_axis_label.as_str())
+            .y_label_formatter(&value_label_formatter)
+            .y_labels(plot_settings.y_labels)
+            .y_label_style((plot_settings.font_family.as_str(), plot_settings.y_label_style).into_font())
+            .draw().with_context(|| "Drawing plot problems (weird...)")?;
+
+        let colors = get_colors(param, plot_settings, file_datas.len());
+        let name_style = TextStyle::from((plot_settings.font_family.as_str(), plot_settings.x_label_style).into_font()).pos(Pos::new(HPos::Center, VPos::Top));
+        for (idx, (file_data, stat)) in file_datas.iter().zip(&stats).enumerate() {
+            let colour = colors[idx].style.clone();
+            let center = idx as f64;
+            let half_box = 0.3;
+            let half_whisker = 0.15;
+
+            chart.draw_series(vec![
+                Rectangle::new([(center - half_box, stat.p25th_percentile), (center + half_box, stat.p75th_percentile)], colour.color.mix(0.3).filled()),
+                Rectangle::new([(center - half_box, stat.p25th_percentile), (center + half_box, stat.p75th_percentile)], ShapeStyle { filled: false, ..colour.clone() })
+            ]).with_context(|| "Box plot drawing problems (weird...)")?;
+
+            chart.draw_series(vec![
+                // Whiskers
+                PathElement::new(vec![(center, stat.p1st_percentile), (center, stat.p25th_percentile)], colour.clone()),
+                PathElement::new(vec![(center, stat.p75th_percentile), (center, stat.p99th_percentile)], colour.clone()),
+                PathElement::new(vec![(center - half_whisker, stat.p1st_percentile), (center + half_whisker, stat.p1st_percentile)], colour.clone()),
+                PathElement::new(vec![(center - half_whisker, stat.p99th_percentile), (center + half_whisker, stat.p99th_percentile)], colour.clone()),
+                // 90th percentile and median
+                PathElement::new(vec![(center - half_whisker, stat.p90th_percentile), (center + half_whisker, stat.p90th_percentile)], colour.clone()),
+                PathElement::new(vec![(center - half_box, stat.median), (center + half_box, stat.median)], ShapeStyle { stroke_width: colour.stroke_width + 2, ..colour.clone() })
+            ]).with_context(|| "Box plot drawing problems (weird...)")?;
+
+            let name_position = chart.backend_coord(&(center, min_value - margin));
+            root.draw(&Text::new(file_data.file_name.clone(), (name_position.0, name_position.1 + 10), name_style.clone()))
+                .with_context(|| "Box plot label drawing problems (weird...)")?;
+        }
+    });
+
+    Ok(())
+}
+
+// Average, 90th and 99th percentile of every metric across the recorded runs, with the run on
+// the X axis. Every file name gets its own lines, so that only the same file is compared.
+pub fn generate_history_plots(runs: &[HistoryRun], metrics: &[HistoryMetric], param: &Parameters) -> Result<()> {
+    let start = Instant::now();
+    info!("Generating history plots..");
+
+    let plot_settings = get_settings(0, 0, param);
+    metrics.par_iter().for_each(|metric| {
+        if let Err(e) = create_history_plot(runs, metric, param, &plot_settings) {
+            panic!("{:?}", e)
+        }
+    });
+
+    debug!("Parallel Generate history plots duration: {:?}", start.elapsed());
+    Ok(())
+}
+
+static HISTORY_STATISTICS: [&str; 3] = ["Average", "90th percentile", "99th percentile"];
+
+// A statistic of a file across the runs, at the position of each run
+struct HistorySeries {
+    statistic: usize,
+    file: usize,
+    points: Vec<(f64, f64)>
+}
+
+fn history_value(point: &HistoryPoint, statistic: usize) -> f64 {
+    match statistic {
+        0 => point.average,
+        1 => point.p90th_percentile,
+        _ => point.p99th_percentile
+    }
+}
+
+fn create_history_plot(runs: &[HistoryRun], metric: &HistoryMetric, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
+    debug!("Creating history plot for {}", metric.name);
+
+    let image_name = metric.name.get_file_name("_history");
+    let mut file_names: Vec<&str> = metric.points.iter().map(|point| point.file_name.as_str()).collect();
+    file_names.sort_unstable();
+    file_names.dedup();
+
+    let mut series: Vec<HistorySeries> = Vec::new();
+    for statistic in 0..HISTORY_STATISTICS.len() {
+        for (file, file_name) in file_names.iter().enumerate() {
+            let points = metric.points.iter()
+                .filter(|point| point.file_name == *file_name && history_value(point, statistic).is_finite())
+                .filter_map(|point| runs.iter().position(|run| run.id == point.run_id)
+                    .map(|position| (position as f64, history_value(point, statistic))))
+                .collect();
+            series.push(HistorySeries { statistic, file, points });
+        }
+    }
+
+    let finite_values = series.iter().flat_map(|line| line.points.iter().map(|point| point.1));
+    let min_value = finite_values.clone().fold(f64::INFINITY, f64::min);
+    let max_value = finite_values.fold(f64::NEG_INFINITY, f64::max);
+    let (min_value, max_value) = if min_value > max_value { (0.0, 1.0) } else { (min_value, max_value) };
+    let margin = if max_value > min_value { (max_value - min_value) * 0.05 } else { 1.0 };
+
+    let amount = runs.len();
+    let width = std::cmp::max(plot_settings.minimum_width, plot_settings.y_label_area_size + amount as u32 * 20);
+    draw_image!(param, image_name, (width, plot_settings.height), |root| {
+        root.fill(&plot_settings.background_colour()).with_context(|| "Filling plot color problems (weird...)")?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(plot_settings.margin)
+            .x_label_area_size(plot_settings.x_label_area_size)
+            .y_label_area_size(plot_settings.y_label_area_size)
+            .caption(&metric.name, (plot_settings.font_family.as_str(), plot_settings.caption_size).into_font())
+            .build_ranged(-0.5f64..(amount as f64 - 0.5), (min_value - margin)..(max_value + margin)).with_context(|| "Building plot problems (weird...)")?;
+
+        // Only the ticks that fall on a run are labelled, with its id
+        let run_label_formatter = |x: &f64| {
+            let position = x.round();
+            match runs.get(position as usize) {
+                Some(run) if position >= 0.0 && (x - position).abs() < 1e-6 => format!("#{}", run.id),
+                _ => String::new()
+            }
+        };
+        let value_label_formatter = |value: &f64| format_unit(*value, &metric.unit);
+        style_grid(&mut chart.configure_mesh(), plot_settings)
+            .x_desc("Run")
+            .x_label_formatter(&run_label_formatter)
+            .y_desc(plot_settings.y_axis_label.as_str())
+            .y_label_formatter(&value_label_formatter)
+            .x_labels(plot_settings.x_labels.min(amount))
+            .x_label_style((plot_settings.font_family.as_str(), plot_settings.x_label_style).into_font())
+            .y_labels(plot_settings.y_labels)
+            .y_label_style((plot_settings.font_family.as_str(), plot_settings.y_label_style).into_font())
+            .draw().with_context(|| "Drawing plot problems (weird...)")?;
+
+        // Statistics have their own colour, files their own dash pattern
+        let colors = get_colors(param, plot_settings, HISTORY_STATISTICS.len());
+        for line in &series {
+            let mut colour = colors[line.statistic].clone();
+            colour.dash = match line.file {
+                0 => None,
+                file => Some(DASH_PATTERNS[(file - 1) % DASH_PATTERNS.len()])
+            };
+            let label = match file_names.len() {
+                1 => HISTORY_STATISTICS[line.statistic].to_string(),
+                _ => format!("{} ({})", HISTORY_STATISTICS[line.statistic], file_names[line.file])
+            };
+
+            // Runs are few, so every one of them is marked
+            let marker_style = colour.style.filled();
+            chart.draw_series(line.points.iter().map(|point| Circle::new(*point, 4, marker_style.clone())))
+                .with_context(|| "History marker drawing problems (weird...)")?;
+            let legend_colour = colour.clone();
+            draw_file_line(&mut chart, line.points.clone(), &colour)?
+                .label(label)
+                .legend(move |position| legend_line(position, &legend_colour));
+        }
+
+        draw_legend(&mut chart, plot_settings, SeriesLabelPosition::UpperLeft)?;
+    });
+
+    Ok(())
+}
+
+// Lowest and highest value of the metric across files
+fn value_range(file_datas: &[&FileData], metric: &str) -> (f64, f64) {
+    let mut min_value = f64::INFINITY;
+    let mut max_value = f64::NEG_INFINITY;
+    for file_data in file_datas {
+        for value in &file_data.metrics[metric].data {
+            min_value = min_value.min(*value);
+            max_value = max_value.max(*value);
+        }
+    }
+
+    if min_value > max_value {
+        (0.0, 0.0)
+    } else {
+        (min_value, max_value)
+    }
+}
+
+fn create_heatmap(correlations: &FileCorrelations, param: &Parameters, plot_settings: &PlotterSettings) -> Result<()> {
+    debug!("Creating correlation heatmap for {}", correlations.file_name);
+
+    let image_name = format!("correlation_{}", correlations.file_name).get_file_name("");
+    let matrix = correlations.get_matrix(&param.correlation_method);
+    let amount = correlations.metrics.len();
+    let method_name = match param.correlation_method {
+        CorrelationMethod::Pearson => "Pearson",
+        CorrelationMethod::Spearman => "Spearman"
+    };
+
+    // Rows are labelled with "index name", columns only with the index
+    let row_labels: Vec<String> = correlations.metrics.iter().enumerate()
+        .map(|(idx, metric)| format!("{} {}", idx, metric))
+        .collect();
+    let longest_label = row_labels.iter().map(|label| label.len()).max().unwrap_or(0) as u32;
+    let row_label_area_size = std::cmp::min(longest_label * plot_settings.y_label_style * 6 / 10 + 20, 1200);
+    let caption = format!("{} correlation of {}", method_name, correlations.file_name);
+    let caption_width = caption.len() as u32 * plot_settings.caption_size * 6 / 10 + 40;
+    let cell_size: u32 = 40;
+    let column_label_area_size: u32 = 40;
+    let width = std::cmp::max(row_label_area_size + amount as u32 * cell_size + 40, caption_width);
+    let height = plot_settings.caption_size + column_label_area_size + amount as u32 * cell_size + 40;
+
+    draw_image!(param, image_name, (width, height), |root| {
+        root.fill(&plot_settings.background_colour()).with_context(|| "Filling plot color problems (weird...)")?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .margin(plot_settings.margin)
+            .x_label_area_size(column_label_area_size)
+            .y_label_area_size(row_label_area_size)
+            .caption(&caption, (plot_settings.font_family.as_str(), plot_settings.caption_size).into_font())
+            .build_ranged(0f64..amount as f64, 0f64..amount as f64).with_context(|| "Building plot problems (weird...)")?;
+
+        // The first metric is the top row
+        let mut cells = Vec::new();
+        for (i, row) in matrix.iter().enumerate() {
+            let y = (amount - i - 1) as f64;
+            for (j, value) in row.iter().enumerate() {
+                let x = j as f64;
+                cells.push(Rectangle::new([(x, y), (x + 1.0, y + 1.0)], correlation_colour(*value).filled()));
+            }
+        }
+        chart.draw_series(cells).with_context(|| "Heatmap drawing problems (weird...)")?;
+
+        let label_style = TextStyle::from((plot_settings.font_family.as_str(), plot_settings.y_label_style).into_font());
+        let row_style = label_style.pos(Pos::new(HPos::Right, VPos::Center));
+        let column_style = label_style.pos(Pos::new(HPos::Center, VPos::Top));
+        let value_style = TextStyle::from((plot_settings.font_family.as_str(), 12).into_font()).pos(Pos::new(HPos::Center, VPos::Center));
+        for (i, label) in row_labels.iter().enumerate() {
+            let row_center = chart.backend_coord(&(0.0, (amount - i) as f64 - 0.5));
+            root.draw(&Text::new(label.clone(), (row_center.0 - 10, row_center.1), row_style.clone()))
+                .with_context(|| "Heatmap label drawing problems (weird...)")?;
+
+            let column_center = chart.backend_coord(&(i as f64 + 0.5, 0.0));
+            root.draw(&Text::new(i.to_string(), (column_center.0, column_center.1 + 10), column_style.clone()))
+                .with_context(|| "Heatmap label drawing problems (weird...)")?;
+
+            for (j, value) in matrix[i].iter().enumerate() {
+                let cell_center = chart.backend_coord(&(j as f64 + 0.5, (amount - i) as f64 - 0.5));
+                root.draw(&Text::new(format!("{:.2}", value), cell_center, value_style.clone()))
+                    .with_context(|| "Heatmap value drawing problems (weird...)")?;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+// White for no correlation, red for positive and blue for negative
+fn correlation_colour(value: f64) -> RGBColor {
+    let fade = (255.0 * (1.0 - value.abs().min(1.0))) as u8;
+    if value >= 0.0 {
+        RGBColor(255, fade, fade)
+    } else {
+        RGBColor(fade, fade, 255)
+    }
+}
+
+// Converts seconds to the X axis unit, keeping the fraction
+fn seconds_to_x(seconds: f64, x_axis: &TimeFormat) -> f64 {
+    match x_axis {
+        TimeFormat::Milliseconds => seconds * 1000.0,
+        TimeFormat::Seconds | TimeFormat::Clock => seconds,
+        TimeFormat::Minutes => seconds / 60.0,
+        TimeFormat::Hours => seconds / 3600.0
+    }
+}
+
+// One style per file. When the colours file runs out, new colours are generated or the list
+// starts over, depending on the plot settings. Files that share a colour get a dash pattern.
+fn get_colors(param: &Parameters, plot_settings: &PlotterSettings, amount: usize) -> Vec<FileStyle> {
     let filled = true;
     let stroke_width = plot_settings.stroke_width;
-    let mut result = Vec::new();
+    let mut result: Vec<FileStyle> = Vec::new();
     let mut file_colors = Vec::new();
 
     let file = File::open(&param.plotter_colors_file).unwrap();
     let reader = io::BufReader::new(file).lines();
     for result_line in reader {
-        if let Ok(line) = result_line {
-            if line.is_empty() {
-                continue;
-            }
-            file_colors.push(line.parse::<RGB>().unwrap());
+        let line = match result_line {
+            Ok(line) => line,
+            Err(_) => continue
+        };
+        if line.trim().is_empty() {
+            continue;
         }
+        file_colors.push(line.parse::<Rgb>()
+            .with_context(|| format!("Invalid colour {:?} in {:?}", line, param.plotter_colors_file)).unwrap());
     }
 
-    for rgb in file_colors {
-        let shape_style = ShapeStyle { color: RGBColor(rgb.0, rgb.1, rgb.2).to_rgba(), filled, stroke_width };
-        result.push(shape_style);
+    for idx in 0..amount {
+        let rgb = match (file_colors.get(idx), &plot_settings.extra_colours) {
+            (Some(rgb), _) => RGBColor(rgb.0, rgb.1, rgb.2),
+            (None, ExtraColours::Repeat) if !file_colors.is_empty() => {
+                let rgb = &file_colors[idx % file_colors.len()];
+                RGBColor(rgb.0, rgb.1, rgb.2)
+            }
+            // The golden angle keeps consecutive hues far apart
+            _ => hcl_colour(15.0 + (idx - file_colors.len()) as f64 * 137.508, 65.0, 55.0)
+        };
+
+        let repeats = result.iter().filter(|other| other.rgb == (rgb.0, rgb.1, rgb.2)).count();
+        result.push(FileStyle {
+            style: ShapeStyle { color: rgb.to_rgba(), filled, stroke_width },
+            rgb: (rgb.0, rgb.1, rgb.2),
+            dash: match repeats {
+                0 => None,
+                _ => Some(DASH_PATTERNS[(repeats - 1) % DASH_PATTERNS.len()])
+            }
+        });
     }
 
     result
 }
 
+// Drawn and skipped pixels of each dash pattern, in the order they are handed out
+static DASH_PATTERNS: [(u32, u32); 4] = [(12, 6), (4, 4), (20, 6), (2, 6)];
+
+#[derive(Clone)]
+struct FileStyle {
+    style: ShapeStyle,
+    rgb: (u8, u8, u8),
+    dash: Option<(u32, u32)>
+}
+
+// Colour of the given hue (in degrees) in the HCL colour space, where colours with the same
+// chroma and luminance look equally bright
+fn hcl_colour(hue: f64, chroma: f64, luminance: f64) -> RGBColor {
+    let hue = hue.to_radians();
+    let (a, b) = (chroma * hue.cos(), chroma * hue.sin());
 
-pub struct RGB(pub u8, pub u8, pub u8);
+    // CIELAB to XYZ, with the D65 white point
+    let fy = (luminance + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let inverse = |t: f64| if t > 6.0 / 29.0 { t * t * t } else { 3.0 * (6.0f64 / 29.0).powi(2) * (t - 4.0 / 29.0) };
+    let (x, y, z) = (0.95047 * inverse(fx), inverse(fy), 1.08883 * inverse(fz));
+
+    // XYZ to sRGB
+    let channel = |linear: f64| {
+        let linear = linear.clamp(0.0, 1.0);
+        let gamma = if linear <= 0.0031308 { 12.92 * linear } else { 1.055 * linear.powf(1.0 / 2.4) - 0.055 };
+        (gamma * 255.0).round() as u8
+    };
+    RGBColor(
+        channel(3.2406 * x - 1.5372 * y - 0.4986 * z),
+        channel(-0.9689 * x + 1.8758 * y + 0.0415 * z),
+        channel(0.0557 * x - 0.2040 * y + 1.0570 * z)
+    )
+}
+
+// Draws the line of a file. Dashes are measured in pixels, so that they look the same on any axis.
+// Lines are drawn one segment at a time: plotters joins the segments of thick paths with unlimited
+// miters, which shoot far out of the chart on spikes.
+fn draw_file_line<'a, 'c, DB, X, Y>(chart: &'c mut ChartContext<'a, DB, RangedCoord<X, Y>>, points: Vec<(f64, f64)>, file_style: &FileStyle) -> Result<&'c mut SeriesAnno<'a, DB>>
+    where DB: DrawingBackend + 'a, DB::ErrorType: 'static, X: Ranged<ValueType = f64>, Y: Ranged<ValueType = f64> {
+
+    let dash = match file_style.dash {
+        Some(dash) => dash,
+        None => return chart.draw_series(line_segments(&points, &file_style.style))
+            .with_context(|| "Plot line drawing problems (weird...)")
+    };
+    draw_dashes(chart.plotting_area(), &points, dash, &file_style.style)?;
+
+    // Nothing left to draw, but the line still needs its legend entry
+    chart.draw_series(Vec::<PathElement<(f64, f64)>>::new()).with_context(|| "Plot line drawing problems (weird...)")
+}
+
+// Same as draw_file_line, against the right Y axis
+fn draw_secondary_file_line<'a, 'c, DB, X, Y, SX, SY>(chart: &'c mut DualCoordChartContext<'a, DB, RangedCoord<X, Y>, RangedCoord<SX, SY>>,
+        points: Vec<(f64, f64)>, file_style: &FileStyle) -> Result<&'c mut SeriesAnno<'a, DB>>
+    where DB: DrawingBackend + 'a, DB::ErrorType: 'static, X: Ranged<ValueType = f64>, Y: Ranged<ValueType = f64>, SX: Ranged<ValueType = f64>, SY: Ranged<ValueType = f64> {
+
+    let dash = match file_style.dash {
+        Some(dash) => dash,
+        None => return chart.draw_secondary_series(line_segments(&points, &file_style.style))
+            .with_context(|| "Plot line drawing problems (weird...)")
+    };
+    draw_dashes(chart.secondary_plotting_area(), &points, dash, &file_style.style)?;
+
+    chart.draw_secondary_series(Vec::<PathElement<(f64, f64)>>::new()).with_context(|| "Plot line drawing problems (weird...)")
+}
+
+fn draw_dashes<DB, X, Y>(area: &DrawingArea<DB, RangedCoord<X, Y>>, points: &[(f64, f64)], (on, off): (u32, u32), style: &ShapeStyle) -> Result<()>
+    where DB: DrawingBackend, DB::ErrorType: 'static, X: Ranged<ValueType = f64>, Y: Ranged<ValueType = f64> {
+
+    let base_pixel = area.get_base_pixel();
+    let pixels: Vec<(f64, f64)> = points.iter()
+        .map(|point| area.map_coordinate(point))
+        .map(|(x, y)| ((x - base_pixel.0) as f64, (y - base_pixel.1) as f64))
+        .collect();
+
+    let pixel_area = area.strip_coord_spec();
+    for dash in dash_segments(&pixels, on as f64, off as f64) {
+        let dash: Vec<(i32, i32)> = dash.into_iter().map(|(x, y)| (x.round() as i32, y.round() as i32)).collect();
+        for segment in line_segments(&dash, style) {
+            pixel_area.draw(&segment).with_context(|| "Plot line drawing problems (weird...)")?;
+        }
+    }
 
-impl FromStr for RGB {
-    type Err = ParseIntError;
+    Ok(())
+}
+
+fn line_segments<C: Copy>(points: &[C], style: &ShapeStyle) -> Vec<PathElement<C>> {
+    points.windows(2)
+        .map(|pair| PathElement::new(pair.to_vec(), style.clone()))
+        .collect()
+}
+
+// Splits a line into the pieces drawn by a dash pattern
+fn dash_segments(points: &[(f64, f64)], on: f64, off: f64) -> Vec<Vec<(f64, f64)>> {
+    let mut dashes = Vec::new();
+    let mut dash: Vec<(f64, f64)> = points.iter().take(1).cloned().collect();
+    let mut drawing = true;
+    // Pixels until the pattern switches between drawing and skipping
+    let mut left = on;
+
+    for pair in points.windows(2) {
+        let (mut x, mut y) = pair[0];
+        let (end_x, end_y) = pair[1];
+        let mut length = ((end_x - x).powi(2) + (end_y - y).powi(2)).sqrt();
+
+        while length > left {
+            let ratio = left / length;
+            x += (end_x - x) * ratio;
+            y += (end_y - y) * ratio;
+            length -= left;
+
+            dash.push((x, y));
+            if drawing {
+                dashes.push(std::mem::take(&mut dash));
+                left = off;
+            } else {
+                dash = vec![(x, y)];
+                left = on;
+            }
+            drawing = !drawing;
+        }
+
+        left -= length;
+        if drawing {
+            dash.push((end_x, end_y));
+        }
+    }
+
+    if drawing && dash.len() > 1 {
+        dashes.push(dash);
+    }
+
+    dashes
+}
+
+// Legend sample of the line of a file, with a gap in the middle when it is dashed
+fn legend_line<DB: DrawingBackend>((x, y): BackendCoord, file_style: &FileStyle) -> ComposedElement<BackendCoord, DB, PathElement<BackendCoord>, PathElement<BackendCoord>> {
+    let (first_end, second_start) = match file_style.dash {
+        Some(_) => (7, 13),
+        None => (10, 10)
+    };
+
+    EmptyElement::at((x, y))
+        + PathElement::new(vec![(0, 0), (first_end, 0)], file_style.style.clone())
+        + PathElement::new(vec![(second_start, 0), (20, 0)], file_style.style.clone())
+}
+
+
+#[derive(Debug)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+// Colours are written as "r, g, b", "#rrggbb" or by name
+impl FromStr for Rgb {
+    type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(hex) = s.strip_prefix('#') {
+            // Checked before slicing, which counts bytes
+            if hex.len() != 6 || !hex.is_ascii() {
+                bail!("Hex colours should be written as #rrggbb");
+            }
+            let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16);
+            return Ok(Rgb(channel(0)?, channel(2)?, channel(4)?));
+        }
+
+        if let Some(rgb) = named_colour(&s.to_lowercase()) {
+            return Ok(rgb);
+        }
+
         let rgb: Vec<u8> = s.split(',')
                                  .map(|s| s.trim())
-                                 .map(|s| s.parse::<u8>().unwrap())
-                                 .collect();
+                                 .map(|s| s.parse::<u8>())
+                                 .collect::<Result<_, _>>()?;
+        if rgb.len() != 3 {
+            bail!("Colours should be written as <r, g, b>, <#rrggbb> or a name");
+        }
 
-        Ok(RGB(rgb[0], rgb[1], rgb[2]))
+        Ok(Rgb(rgb[0], rgb[1], rgb[2]))
     }
 }
 
+// Values of the CSS colours of the same name, the names that share a line are CSS aliases
+fn named_colour(name: &str) -> Option<Rgb> {
+    let rgb = match name {
+        "black" => Rgb(0, 0, 0),
+        "white" => Rgb(255, 255, 255),
+        "gray" | "grey" => Rgb(128, 128, 128),
+        "silver" => Rgb(192, 192, 192),
+        "red" => Rgb(255, 0, 0),
+        "darkred" => Rgb(139, 0, 0),
+        "maroon" => Rgb(128, 0, 0),
+        "green" => Rgb(0, 128, 0),
+        "lime" => Rgb(0, 255, 0),
+        "darkgreen" => Rgb(0, 100, 0),
+        "olive" => Rgb(128, 128, 0),
+        "blue" => Rgb(0, 0, 255),
+        "darkblue" => Rgb(0, 0, 139),
+        "navy" => Rgb(0, 0, 128),
+        "teal" => Rgb(0, 128, 128),
+        "cyan" | "aqua" => Rgb(0, 255, 255),
+        "magenta" | "fuchsia" => Rgb(255, 0, 255),
+        "purple" => Rgb(128, 0, 128),
+        "yellow" => Rgb(255, 255, 0),
+        "gold" => Rgb(255, 215, 0),
+        "orange" => Rgb(255, 165, 0),
+        "brown" => Rgb(165, 42, 42),
+        "pink" => Rgb(255, 192, 203),
+        _ => return None
+    };
+
+    Some(rgb)
+}
+
 fn get_settings(_width: usize, _height: usize, param: &Parameters) -> PlotterSettings {
     // Initialize with default values
     let mut plot_settings = PlotterSettings {
         // Plot size
         minimum_width: 1500,
+        height: 768,
+        // Space around every chart
+        margin: 10,
+        // Look
+        font_family: "sans-serif".to_string(),
+        background_colour: Rgb(255, 255, 255),
+        grid_lines: GridLines::All,
+        grid_colour: Rgb(0, 0, 0),
+        extra_colours: ExtraColours::Generate,
         // Labels
         caption_size: 50,      // Graph label
         x_label_area_size: 70, // X label size
@@ -198,40 +1465,91 @@ fn get_settings(_width: usize, _height: usize, param: &Parameters) -> PlotterSet
         y_label_style: 20,    // Y axis value size
         // File to line legend label
         legend_label_font: 20,
+        legend_position: None,
+        // Axis descriptions
+        x_axis_label: None,
+        y_axis_label: "Value".to_string(),
         // Width of each line
-        stroke_width: 2
+        stroke_width: 2,
+        // Which trend lines are drawn
+        trend_lines: TrendLines::Significant,
+        // Histogram and cumulative distribution charts
+        distribution_width: 900,
+        distribution_height: 600,
+        histogram_bins: 40
     };
 
     let file = File::open(&param.plotter_config_file).with_context(|| format!("Could not open file {:?}", param.plotter_config_file)).unwrap();
     let reader = io::BufReader::new(file).lines();
     for result_line in reader {
-        if let Ok(line) = result_line {
-            if line.is_empty() {
-                continue;
-            }
-            if line.contains("//") {
-                continue;
-            }
+        let line = match result_line {
+            Ok(line) => line,
+            Err(_) => continue
+        };
+        if line.is_empty() {
+            continue;
+        }
+        if line.contains("//") {
+            continue;
+        }
 
-            let config: Vec<&str> = line.split(':')
-                .map(|s| s.trim())
-                .collect();
-            
-            match config[0] {
-                "minimum_width" => plot_settings.minimum_width = config[1].parse::<u32>().unwrap(),
-                "caption_size" => plot_settings.caption_size = config[1].parse::<u32>().unwrap(),
-                "x_label_area_size" => plot_settings.x_label_area_size = config[1].parse::<u32>().unwrap(),
-                "y_label_area_size" => plot_settings.y_label_area_size = config[1].parse::<u32>().unwrap(),
-
-                "x_labels" => plot_settings.x_labels = config[1].parse::<usize>().unwrap(),
-                "x_label_style" => plot_settings.x_label_style = config[1].parse::<u32>().unwrap(),
-                "y_labels" => plot_settings.y_labels = config[1].parse::<usize>().unwrap(),
-                "y_label_style" => plot_settings.y_label_style = config[1].parse::<u32>().unwrap(),
-
-                "legend_label_font" => plot_settings.legend_label_font = config[1].parse::<u32>().unwrap(),
-                "stroke_width" => plot_settings.stroke_width = config[1].parse::<u32>().unwrap(),
-                _ => ()
+        // Split only once, so that label texts can contain ':'
+        let config: Vec<&str> = line.splitn(2, ':')
+            .map(|s| s.trim())
+            .collect();
+        if config.len() != 2 {
+            warn!("Plot setting line {:?} should be written as <key: value>, ignored", line);
+            continue;
+        }
+
+        match config[0] {
+            "minimum_width" => plot_settings.minimum_width = config[1].parse::<u32>().unwrap(),
+            "height" => plot_settings.height = config[1].parse::<u32>().unwrap(),
+            "margin" => plot_settings.margin = config[1].parse::<u32>().unwrap(),
+            "font_family" => plot_settings.font_family = config[1].to_string(),
+            "background_colour" => plot_settings.background_colour = config[1].parse::<Rgb>().unwrap(),
+            "grid_lines" => plot_settings.grid_lines = match config[1] {
+                "all" => GridLines::All,
+                "major" => GridLines::Major,
+                "none" => GridLines::None,
+                _ => panic!("Wrong grid_lines value. Options are <all>, <major> or <none>")
+            },
+            "grid_colour" => plot_settings.grid_colour = config[1].parse::<Rgb>().unwrap(),
+            "extra_colours" => plot_settings.extra_colours = match config[1] {
+                "generate" => ExtraColours::Generate,
+                "repeat" => ExtraColours::Repeat,
+                _ => panic!("Wrong extra_colours value. Options are <generate> or <repeat>")
+            },
+            "caption_size" => plot_settings.caption_size = config[1].parse::<u32>().unwrap(),
+            "x_label_area_size" => plot_settings.x_label_area_size = config[1].parse::<u32>().unwrap(),
+            "y_label_area_size" => plot_settings.y_label_area_size = config[1].parse::<u32>().unwrap(),
+
+            "x_labels" => plot_settings.x_labels = config[1].parse::<usize>().unwrap(),
+            "x_label_style" => plot_settings.x_label_style = config[1].parse::<u32>().unwrap(),
+            "y_labels" => plot_settings.y_labels = config[1].parse::<usize>().unwrap(),
+            "y_label_style" => plot_settings.y_label_style = config[1].parse::<u32>().unwrap(),
+
+            "legend_label_font" => plot_settings.legend_label_font = config[1].parse::<u32>().unwrap(),
+            "legend_position" => {
+                if parse_legend_position(config[1]).is_none() {
+                    panic!("Wrong legend_position value. Options are <upper_left>, <upper_middle>, <upper_right>, \
+                        <middle_left>, <middle_middle>, <middle_right>, <lower_left>, <lower_middle> or <lower_right>")
+                }
+                plot_settings.legend_position = Some(config[1].to_string())
             }
+            "x_axis_label" => plot_settings.x_axis_label = Some(config[1].to_string()),
+            "y_axis_label" => plot_settings.y_axis_label = config[1].to_string(),
+            "stroke_width" => plot_settings.stroke_width = config[1].parse::<u32>().unwrap(),
+            "distribution_width" => plot_settings.distribution_width = config[1].parse::<u32>().unwrap(),
+            "distribution_height" => plot_settings.distribution_height = config[1].parse::<u32>().unwrap(),
+            "histogram_bins" => plot_settings.histogram_bins = config[1].parse::<usize>().unwrap(),
+            "trend_lines" => plot_settings.trend_lines = match config[1] {
+                "all" => TrendLines::All,
+                "significant" => TrendLines::Significant,
+                "none" => TrendLines::None,
+                _ => panic!("Wrong trend_lines value. Options are <all>, <significant> or <none>")
+            },
+            _ => warn!("Unknown plot setting {:?}, ignored", config[0])
         }
     }
 
@@ -239,10 +1557,70 @@ fn get_settings(_width: usize, _height: usize, param: &Parameters) -> PlotterSet
 
     plot_settings
 }
+
+impl PlotterSettings {
+
+    fn background_colour(&self) -> RGBColor {
+        RGBColor(self.background_colour.0, self.background_colour.1, self.background_colour.2)
+    }
+
+    fn legend_position(&self, default: SeriesLabelPosition) -> SeriesLabelPosition {
+        match &self.legend_position {
+            Some(position) => parse_legend_position(position).unwrap(),
+            None => default
+        }
+    }
+
+    fn x_axis_label(&self, x_axis: &TimeFormat) -> String {
+        match (&self.x_axis_label, x_axis) {
+            (Some(label), _) => label.clone(),
+            (None, TimeFormat::Milliseconds) => "Time (milliseconds)".to_string(),
+            (None, TimeFormat::Seconds) => "Time (seconds)".to_string(),
+            (None, TimeFormat::Minutes) => "Time (minutes)".to_string(),
+            (None, TimeFormat::Hours) => "Time (hours)".to_string(),
+            (None, TimeFormat::Clock) => "Time of day".to_string()
+        }
+    }
+}
+
+fn parse_legend_position(position: &str) -> Option<SeriesLabelPosition> {
+    match position {
+        "upper_left" => Some(SeriesLabelPosition::UpperLeft),
+        "upper_middle" => Some(SeriesLabelPosition::UpperMiddle),
+        "upper_right" => Some(SeriesLabelPosition::UpperRight),
+        "middle_left" => Some(SeriesLabelPosition::MiddleLeft),
+        "middle_middle" => Some(SeriesLabelPosition::MiddleMiddle),
+        "middle_right" => Some(SeriesLabelPosition::MiddleRight),
+        "lower_left" => Some(SeriesLabelPosition::LowerLeft),
+        "lower_middle" => Some(SeriesLabelPosition::LowerMiddle),
+        "lower_right" => Some(SeriesLabelPosition::LowerRight),
+        _ => None
+    }
+}
+
+// Major grid lines are drawn stronger than the minor ones, both in the grid colour
+fn style_grid<'m, 'a, 'b, X: Ranged, Y: Ranged, DB: DrawingBackend>(mesh: &'m mut MeshStyle<'a, 'b, X, Y, DB>, plot_settings: &PlotterSettings) -> &'m mut MeshStyle<'a, 'b, X, Y, DB> {
+    let grid_colour = RGBColor(plot_settings.grid_colour.0, plot_settings.grid_colour.1, plot_settings.grid_colour.2);
+    match plot_settings.grid_lines {
+        GridLines::All => mesh.line_style_1(&grid_colour.mix(0.2)).line_style_2(&grid_colour.mix(0.1)),
+        GridLines::Major => mesh.line_style_1(&grid_colour.mix(0.2)).line_style_2(&TRANSPARENT),
+        GridLines::None => mesh.disable_mesh()
+    }
+}
 #[derive(Debug)]
 struct PlotterSettings {
     // Plot size
     minimum_width: u32,
+    height: u32,
+    // Space around every chart
+    margin: u32,
+    // Look
+    font_family: String,
+    background_colour: Rgb,
+    grid_lines: GridLines,
+    grid_colour: Rgb,
+    // What files get once the colours file runs out
+    extra_colours: ExtraColours,
     // Labels
     caption_size: u32,      // Graph label
     x_label_area_size: u32, // X label size
@@ -254,6 +1632,38 @@ struct PlotterSettings {
     y_label_style: u32,    // Y axis value size
     // File to line legend label
     legend_label_font: u32,
+    // When not set, every chart uses its own default position
+    legend_position: Option<String>,
+    // Axis descriptions, the Y axis one also names the value axis of the distribution charts.
+    // When not set, the X axis one follows the time format.
+    x_axis_label: Option<String>,
+    y_axis_label: String,
     // Width of each line
-    stroke_width: u32
+    stroke_width: u32,
+    // Which trend lines are drawn
+    trend_lines: TrendLines,
+    // Histogram and cumulative distribution charts
+    distribution_width: u32,
+    distribution_height: u32,
+    histogram_bins: usize
+}
+
+#[derive(Debug)]
+enum ExtraColours {
+    Generate,
+    Repeat
+}
+
+#[derive(Debug)]
+enum GridLines {
+    All,
+    Major,
+    None
+}
+
+#[derive(Debug)]
+enum TrendLines {
+    All,
+    Significant,
+    None
 }
\ No newline at end of file
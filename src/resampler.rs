@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::time::Instant;
+use anyhow::{bail, Result};
+use chrono::{Duration, NaiveDateTime};
+use log::{debug, info, warn};
+
+use crate::data_loader::{LoadedData, FileData, Metric};
+use crate::parameters::{Parameters, Aggregation};
+
+// Most intervals a file can be resampled to, so that a tiny interval over a long capture fails
+// instead of running out of memory
+static MAX_RESAMPLE_INTERVALS: i64 = 1_000_000;
+// Bounds of the resample interval in seconds. The grid works in microseconds, which must fit in
+// an i64 (about 292,000 years).
+static MIN_RESAMPLE_INTERVAL: f64 = 0.000_001;
+static MAX_RESAMPLE_INTERVAL: f64 = 9_000_000_000_000.0;
+
+pub fn resample_data(loaded_data: &mut LoadedData, param: &Parameters) -> Result<()> {
+    let settings = &param.resample;
+    if settings.interval.is_none() && settings.align_metric.is_none() {
+        return Ok(());
+    }
+
+    if let Some(interval) = settings.interval {
+        verify_interval(interval)?;
+    }
+
+    let start = Instant::now();
+    info!("Resampling data..");
+
+    for file_data in loaded_data.get_all_data_mut() {
+        if let Some(align_metric) = &settings.align_metric {
+            align_on_marker(file_data, align_metric, settings.align_threshold);
+        }
+
+        if let Some(interval) = settings.interval {
+            resample_file(file_data, interval, &settings.aggregation)?;
+        }
+    }
+
+    debug!("Sequencial resampling duration: {:?}", start.elapsed());
+    Ok(())
+}
+
+// NaN is outside of every range, so it is rejected along with infinity
+fn verify_interval(interval: f64) -> Result<()> {
+    if !(MIN_RESAMPLE_INTERVAL..=MAX_RESAMPLE_INTERVAL).contains(&interval) {
+        bail!("The resample interval must be between {} and {} seconds, got {}", MIN_RESAMPLE_INTERVAL, MAX_RESAMPLE_INTERVAL, interval);
+    }
+    Ok(())
+}
+
+// Discards every sample before the first one where the marker metric goes above the threshold,
+// so that the marker becomes the first timestamp (X = 0) of the file.
+fn align_on_marker(file_data: &mut FileData, align_metric: &str, threshold: f64) {
+    let mut candidates: Vec<&String> = file_data.metrics.keys()
+        .filter(|name| name.contains(align_metric))
+        .collect();
+    candidates.sort();
+
+    let marker_metric = match candidates.first() {
+        Some(name) => (*name).clone(),
+        None => {
+            warn!("{} has no metric that contains {}, aligning on the first sample", file_data.file_name, align_metric);
+            return;
+        }
+    };
+
+    let marker_idx = match file_data.metrics[&marker_metric].data.iter().position(|value| *value > threshold) {
+        Some(idx) => idx,
+        None => {
+            warn!("{} never goes above {} in {}, aligning on the first sample", marker_metric, threshold, file_data.file_name);
+            return;
+        }
+    };
+
+    debug!("{} aligned on {} at sample {}", file_data.file_name, marker_metric, marker_idx);
+    file_data.timestamps.data.drain(..marker_idx);
+    for metric in file_data.metrics.values_mut() {
+        metric.data.drain(..marker_idx);
+    }
+}
+
+// Places the samples on a grid that starts at the first timestamp, combining the samples of each
+// interval with the aggregation. Intervals without samples repeat the previous value.
+// The grid is common to every file in elapsed time: sample i of every file is i intervals after
+// its start (or marker), which is how the files are overlaid and compared.
+fn resample_file(file_data: &mut FileData, interval: f64, aggregation: &Aggregation) -> Result<()> {
+    let timestamps = &file_data.timestamps.data;
+    let first_timestamp = match timestamps.first() {
+        Some(time) => *time,
+        None => return Ok(())
+    };
+
+    // Clock changes, such as the end of daylight saving time, can make timestamps go back.
+    // Samples before the first one have no interval and are skipped, the others are combined
+    // with the samples of their interval.
+    let interval_micros = (interval * 1_000_000.0) as i64;
+    let buckets: Vec<Option<usize>> = timestamps.iter()
+        .map(|time| {
+            let micros = time.signed_duration_since(first_timestamp).num_microseconds().unwrap_or(i64::MAX);
+            match micros / interval_micros {
+                bucket if bucket < 0 => Ok(None),
+                bucket if bucket >= MAX_RESAMPLE_INTERVALS => bail!("Resampling {} every {} seconds needs more than {} intervals, use a larger interval",
+                    file_data.file_name, interval, MAX_RESAMPLE_INTERVALS),
+                bucket => Ok(Some(bucket as usize))
+            }
+        })
+        .collect::<Result<_>>()?;
+    let bucket_amount = buckets.iter().flatten().max().unwrap() + 1;
+
+    let skipped = buckets.iter().filter(|bucket| bucket.is_none()).count();
+    let backwards = timestamps.windows(2).filter(|pair| pair[1] < pair[0]).count();
+    if backwards > 0 {
+        warn!("{} goes back in time {} times (clock change?), {} samples before the first one are skipped",
+            file_data.file_name, backwards, skipped);
+    }
+
+    let mut resampled_metrics: HashMap<String, Metric<f64>> = HashMap::new();
+    for (name, metric) in &file_data.metrics {
+        let mut grouped: Vec<Vec<f64>> = vec![Vec::new(); bucket_amount];
+        for (bucket, value) in buckets.iter().zip(&metric.data) {
+            if let Some(bucket) = bucket {
+                grouped[*bucket].push(*value);
+            }
+        }
+
+        let mut data = Vec::with_capacity(bucket_amount);
+        let mut previous = 0.0;
+        for values in grouped {
+            if !values.is_empty() {
+                previous = aggregate(&values, aggregation);
+            }
+            data.push(previous);
+        }
+
+        resampled_metrics.insert(name.clone(), Metric { data, name: metric.name.clone() });
+    }
+
+    let resampled_timestamps: Vec<NaiveDateTime> = (0..bucket_amount)
+        .map(|bucket| first_timestamp + Duration::microseconds(bucket as i64 * interval_micros))
+        .collect();
+
+    debug!("{} resampled from {} to {} samples", file_data.file_name, timestamps.len(), bucket_amount);
+    file_data.metrics = resampled_metrics;
+    file_data.timestamps.data = resampled_timestamps;
+    Ok(())
+}
+
+fn aggregate(values: &[f64], aggregation: &Aggregation) -> f64 {
+    match aggregation {
+        Aggregation::Mean => values.iter().sum::<f64>() / values.len() as f64,
+        Aggregation::Max => values.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+        Aggregation::Last => *values.last().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(seconds: &[i64], values: &[f64]) -> FileData {
+        let start = NaiveDateTime::parse_from_str("2020-01-01 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let mut metrics = HashMap::new();
+        metrics.insert("cpu".to_string(), Metric { data: values.to_vec(), name: "cpu".to_string() });
+        FileData {
+            metrics,
+            timestamps: Metric { data: seconds.iter().map(|second| start + Duration::seconds(*second)).collect(), name: "time".to_string() },
+            file_name: "test.csv".to_string()
+        }
+    }
+
+    fn elapsed_seconds(file_data: &FileData) -> Vec<i64> {
+        let first = file_data.timestamps.data[0];
+        file_data.timestamps.data.iter().map(|time| time.signed_duration_since(first).num_seconds()).collect()
+    }
+
+    #[test]
+    fn combines_the_samples_of_each_interval() {
+        let mut mean = file(&[0, 1, 2, 3, 4, 5], &[1.0, 3.0, 5.0, 7.0, 2.0, 4.0]);
+        resample_file(&mut mean, 2.0, &Aggregation::Mean).unwrap();
+        assert_eq!(elapsed_seconds(&mean), vec![0, 2, 4]);
+        assert_eq!(mean.metrics["cpu"].data, vec![2.0, 6.0, 3.0]);
+
+        let mut max = file(&[0, 1, 2, 3, 4, 5], &[1.0, 3.0, 5.0, 7.0, 2.0, 4.0]);
+        resample_file(&mut max, 2.0, &Aggregation::Max).unwrap();
+        assert_eq!(max.metrics["cpu"].data, vec![3.0, 7.0, 4.0]);
+
+        let mut last = file(&[0, 1, 2, 3, 4, 5], &[1.0, 3.0, 5.0, 7.0, 2.0, 4.0]);
+        resample_file(&mut last, 2.0, &Aggregation::Last).unwrap();
+        assert_eq!(last.metrics["cpu"].data, vec![3.0, 7.0, 4.0]);
+    }
+
+    #[test]
+    fn fills_gaps_with_the_previous_value() {
+        let mut file_data = file(&[0, 1, 5], &[1.0, 2.0, 8.0]);
+        resample_file(&mut file_data, 1.0, &Aggregation::Mean).unwrap();
+        assert_eq!(elapsed_seconds(&file_data), vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(file_data.metrics["cpu"].data, vec![1.0, 2.0, 2.0, 2.0, 2.0, 8.0]);
+    }
+
+    #[test]
+    fn skips_samples_before_the_first_one() {
+        // The clock goes back 10 seconds after the third sample
+        let mut file_data = file(&[0, 1, 2, -8, -7, 3], &[1.0, 2.0, 3.0, 50.0, 60.0, 4.0]);
+        resample_file(&mut file_data, 1.0, &Aggregation::Mean).unwrap();
+        assert_eq!(elapsed_seconds(&file_data), vec![0, 1, 2, 3]);
+        assert_eq!(file_data.metrics["cpu"].data, vec![1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn fails_when_the_grid_is_too_large() {
+        let mut file_data = file(&[0, 3600 * 24 * 30], &[1.0, 2.0]);
+        assert!(resample_file(&mut file_data, 0.001, &Aggregation::Mean).is_err());
+    }
+
+    #[test]
+    fn rejects_intervals_outside_of_the_bounds() {
+        assert!(verify_interval(1.0).is_ok());
+        assert!(verify_interval(MIN_RESAMPLE_INTERVAL).is_ok());
+        assert!(verify_interval(MAX_RESAMPLE_INTERVAL).is_ok());
+        assert!(verify_interval(0.0).is_err());
+        assert!(verify_interval(-1.0).is_err());
+        assert!(verify_interval(f64::NAN).is_err());
+        assert!(verify_interval(f64::INFINITY).is_err());
+        assert!(verify_interval(1e13).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use std::fs::{self, File};
+use std::time::Instant;
+use std::path::Path;
+use std::io::prelude::*;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use handlebars::{Handlebars, to_json};
+use log::{debug, info, warn};
+use serde::Serialize;
+use serde_json::value::{Map, Value as Json};
+
+use crate::html_renderer::{include_asset, register_helpers, register_partials};
+use crate::parameters::{Parameters, verify_file_exists};
+
+// A report directory of the results directory
+#[derive(Serialize, Default)]
+struct ReportEntry {
+    directory: String,
+    // When the analysis ran, or when the report was written for reports without stats.json
+    generated: String,
+    inputs: Vec<String>,
+    tags: Vec<(String, String)>,
+    // Headline stats, from stats.json
+    has_statistics: bool,
+    metrics: usize,
+    files: usize,
+    samples: u64,
+    duration_seconds: f64,
+    anomalies: usize,
+    change_points: usize,
+    significant_trends: usize
+}
+
+// The index command: index.htm in the results directory, with a row per directory that has a
+// report, newest first
+pub fn generate_results_index(param: &Parameters) -> Result<()> {
+    let start = Instant::now();
+    info!("Generating results index..");
+    let results_directory = Path::new(&param.target_directory);
+
+    let entries = fs::read_dir(results_directory)
+        .with_context(|| format!("Could not read directory {:?}", results_directory))?;
+    let mut reports = Vec::new();
+    for entry in entries {
+        let report_directory = entry.with_context(|| format!("Could not read directory {:?}", results_directory))?.path();
+        if !report_directory.join("index.htm").is_file() {
+            continue;
+        }
+        reports.push(read_report(&report_directory)?);
+    }
+    reports.sort_by(|a, b| b.generated.cmp(&a.generated).then_with(|| b.directory.cmp(&a.directory)));
+    debug!("Found {} reports in {:?}", reports.len(), results_directory);
+    if reports.is_empty() {
+        warn!("There are no reports in {:?}", results_directory);
+    }
+
+    let template_location = verify_file_exists(&"templates/results_index.hbs".to_string());
+    let mut handlebars = Handlebars::new();
+    handlebars.register_template_file("results_index", &template_location)
+        .with_context(|| format!("Could not register handlebars template {:?}", &template_location))?;
+    register_partials(&mut handlebars, &verify_file_exists(&"templates/partials".to_string()))?;
+    register_helpers(&mut handlebars);
+
+    let mut data = Map::new();
+    data.insert("report".to_string(), to_json(&reports));
+    data.insert("single_file".to_string(), to_json(param.single_file));
+    include_asset("report.css", "stylesheet", &mut data, param)?;
+    include_asset("report.js", "report_script", &mut data, param)?;
+
+    let index_path = results_directory.join("index.htm");
+    let index_content = handlebars.render("results_index", &data)
+        .with_context(|| format!("Could not render the template {:?}", &template_location))?;
+    let mut index_file = File::create(&index_path)
+        .with_context(|| format!("Could not create file {:?}", &index_path))?;
+    index_file.write_all(index_content.as_bytes())
+        .with_context(|| format!("Could not write to file {:?}", index_path))?;
+
+    debug!("Sequencial results index generation: {:?}", start.elapsed());
+    Ok(())
+}
+
+// The run metadata and headline stats of a report, from its stats.json. Reports written before
+// stats.json existed are listed with the time of their index.htm.
+fn read_report(report_directory: &Path) -> Result<ReportEntry> {
+    let mut report = ReportEntry {
+        directory: report_directory.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        ..ReportEntry::default()
+    };
+
+    let stats_path = report_directory.join("stats.json");
+    let stats: Option<Json> = match fs::read_to_string(&stats_path) {
+        Ok(content) => match serde_json::from_str(&content) {
+            Ok(stats) => Some(stats),
+            Err(e) => {
+                warn!("Could not read {:?}, listed without stats: {}", &stats_path, e);
+                None
+            }
+        },
+        Err(_) => None
+    };
+
+    let stats = match stats {
+        Some(stats) => stats,
+        None => {
+            let modified = fs::metadata(report_directory.join("index.htm")).and_then(|metadata| metadata.modified())
+                .with_context(|| format!("Could not read the time of {:?}", report_directory.join("index.htm")))?;
+            report.generated = DateTime::<Utc>::from(modified).format("%Y-%m-%dT%H:%M:%S%.3f").to_string();
+            return Ok(report);
+        }
+    };
+
+    let metadata = &stats["metadata"];
+    report.has_statistics = true;
+    report.generated = metadata["generated"].as_str().unwrap_or_default().to_string();
+    report.inputs = metadata["inputs"].as_array().map(|inputs| inputs.iter()
+            .filter_map(|input| input.as_str().map(String::from))
+            .collect())
+        .unwrap_or_default();
+    report.tags = metadata["tags"].as_object().map(|tags| tags.iter()
+            .map(|(key, value)| (key.clone(), value.as_str().unwrap_or_default().to_string()))
+            .collect())
+        .unwrap_or_default();
+    report.samples = metadata["samples"].as_u64().unwrap_or(0);
+    if let Some(files) = metadata["files"].as_array() {
+        report.files = files.len();
+        report.duration_seconds = files.iter()
+            .filter_map(|file| file["duration_seconds"].as_f64())
+            .fold(0.0, f64::max);
+    }
+
+    if let Some(metrics) = stats["metric"].as_object() {
+        report.metrics = metrics.len();
+        for file_stat in metrics.values().filter_map(|metric| metric["files"].as_object()).flat_map(|files| files.values()) {
+            report.anomalies += file_stat["anomalies"].as_array().map(|anomalies| anomalies.len()).unwrap_or(0);
+            report.change_points += file_stat["segments"].as_array().map(|segments| segments.len().saturating_sub(1)).unwrap_or(0);
+            if file_stat["trend"]["significant"].as_bool().unwrap_or(false) {
+                report.significant_trends += 1;
+            }
+        }
+    }
+
+    Ok(report)
+}
@@ -4,25 +4,120 @@ use log::{debug, info};
 use serde_json::value::{Map, Value as Json};
 use statrs::statistics::OrderStatistics;
 use statrs::statistics::Mean;
+use statrs::distribution::{StudentsT, Univariate};
 use serde::Serialize;
 use handlebars::to_json;
 
+use crate::anomaly::{Anomaly, detect_anomalies};
+use crate::changepoint::{Segment, detect_segments};
+use crate::correlation::{FileCorrelations, calculate_correlations};
 use crate::data_loader::LoadedData;
+use crate::metric_options::{Better, MetricOptionsList};
+use crate::parameters::Parameters;
 use crate::FileName;
 
+static TOP_REGRESSIONS_AMOUNT: usize = 10;
+
 pub struct Statistics {
-    // Statistic -> {File : Data}
-    stats: HashMap<String, HashMap<String, Stat>>
+    // Image file name without extension -> Metric statistics
+    stats: HashMap<String, MetricStatistics>,
+    correlations: Vec<FileCorrelations>
 }
 
 #[derive(Serialize, Debug)]
-struct Stat {
-    average: f64,
-    median: f64,
-    p25th_percentile: f64,
-    p75th_percentile: f64,
-    p90th_percentile: f64,
-    p99th_percentile: f64
+pub struct MetricStatistics {
+    pub name: String,
+    // Performance object and counter of the metric, such as Processor and % User Time
+    pub object: String,
+    pub counter: String,
+    // Unit of the values, such as B, /s or %, empty when unknown
+    pub unit: String,
+    // Whether an increase is an improvement, from the better option
+    pub higher_is_better: bool,
+    // Images generated for the metric
+    pub image: String,
+    pub histogram_image: String,
+    pub cdf_image: String,
+    pub boxplot_image: String,
+    // File -> Data
+    pub files: HashMap<String, Stat>
+}
+
+// Metrics of the same performance object, for the index of the report
+#[derive(Serialize)]
+struct MetricGroup<'a> {
+    object: &'a str,
+    metrics: Vec<MetricEntry<'a>>
+}
+
+#[derive(Serialize)]
+struct MetricEntry<'a> {
+    key: &'a str,
+    name: &'a str,
+    counter: &'a str
+}
+
+// Key stats of a metric in a file, for the overview table of the report
+#[derive(Serialize)]
+pub struct OverviewRow<'a> {
+    pub key: &'a str,
+    pub name: &'a str,
+    pub object: &'a str,
+    pub file_name: &'a str,
+    pub image: &'a str,
+    pub unit: &'a str,
+    pub average: f64,
+    pub median: f64,
+    pub p90th_percentile: f64,
+    pub p99th_percentile: f64,
+    pub growth_per_hour: f64,
+    pub significant: bool,
+    pub anomalies: usize,
+    pub change_points: usize
+}
+
+// Change of the average of a metric in a file over the baseline file, in the direction where
+// the metric gets worse
+#[derive(Serialize)]
+pub struct Regression<'a> {
+    pub key: &'a str,
+    pub name: &'a str,
+    pub file_name: &'a str,
+    pub image: &'a str,
+    pub unit: &'a str,
+    pub higher_is_better: bool,
+    pub baseline: f64,
+    pub average: f64,
+    // Negative for metrics where higher is better
+    pub change_percent: f64
+}
+
+#[derive(Serialize, Debug)]
+pub struct Stat {
+    pub average: f64,
+    pub median: f64,
+    pub p1st_percentile: f64,
+    pub p25th_percentile: f64,
+    pub p75th_percentile: f64,
+    pub p90th_percentile: f64,
+    pub p99th_percentile: f64,
+    pub trend: Trend,
+    pub anomalies: Vec<Anomaly>,
+    pub segments: Vec<Segment>
+}
+
+// Least-squares line fitted over the elapsed seconds of a file
+#[derive(Serialize, Debug)]
+pub struct Trend {
+    pub slope: f64,
+    pub intercept: f64,
+    // Half width of the 95% confidence interval of the slope
+    pub slope_confidence_interval: f64,
+    pub growth_per_hour: f64,
+    pub growth_per_hour_confidence_interval: f64,
+    // Whether the confidence interval excludes a flat line and the change points do not explain
+    // the samples better
+    pub significant: bool
 }
 
 impl Statistics {
@@ -30,42 +125,334 @@ impl Statistics {
     pub fn jsonify(&self) -> Map<String, Json> {
         let mut data = Map::new();
         data.insert("metric".to_string(), to_json(&self.stats));
+        data.insert("correlation".to_string(), to_json(&self.correlations));
+        data.insert("metric_group".to_string(), to_json(self.metric_groups()));
+        data.insert("overview".to_string(), to_json(self.overview()));
 
         data
     }
 
-    pub fn calculate_statistics(loaded_data: &LoadedData) -> Statistics {
+    // Metrics grouped by performance object, both sorted by name
+    fn metric_groups(&self) -> Vec<MetricGroup<'_>> {
+        let mut groups: Vec<MetricGroup> = Vec::new();
+        for (key, metric_statistics) in self.sorted_stats() {
+            let entry = MetricEntry { key, name: &metric_statistics.name, counter: &metric_statistics.counter };
+            match groups.last_mut() {
+                Some(group) if group.object == metric_statistics.object => group.metrics.push(entry),
+                _ => groups.push(MetricGroup { object: &metric_statistics.object, metrics: vec![entry] })
+            }
+        }
+
+        groups
+    }
+
+    pub fn overview(&self) -> Vec<OverviewRow<'_>> {
+        let mut rows = Vec::new();
+        for (key, metric_statistics) in self.sorted_stats() {
+            let mut files: Vec<(&String, &Stat)> = metric_statistics.files.iter().collect();
+            files.sort_by(|a, b| a.0.cmp(b.0));
+            for (file_name, stat) in files {
+                rows.push(OverviewRow {
+                    key,
+                    name: &metric_statistics.name,
+                    object: &metric_statistics.object,
+                    file_name,
+                    image: &metric_statistics.image,
+                    unit: &metric_statistics.unit,
+                    average: stat.average,
+                    median: stat.median,
+                    p90th_percentile: stat.p90th_percentile,
+                    p99th_percentile: stat.p99th_percentile,
+                    growth_per_hour: stat.trend.growth_per_hour,
+                    significant: stat.trend.significant,
+                    anomalies: stat.anomalies.len(),
+                    // Every change point starts a new segment
+                    change_points: stat.segments.len().saturating_sub(1)
+                });
+            }
+        }
+
+        rows
+    }
+
+    // The metrics whose average got the most worse from the baseline file to another file: grew,
+    // or shrank for the metrics where higher is better
+    pub fn regressions(&self, baseline_file: &str) -> Vec<Regression<'_>> {
+        let mut regressions = Vec::new();
+        for (key, metric_statistics) in self.sorted_stats() {
+            let baseline = match metric_statistics.files.get(baseline_file) {
+                Some(baseline) if baseline.average != 0.0 => baseline.average,
+                _ => continue
+            };
+
+            for (file_name, stat) in metric_statistics.files.iter().filter(|(file_name, _)| *file_name != baseline_file) {
+                let change_percent = (stat.average - baseline) / baseline.abs() * 100.0;
+                let worse = if metric_statistics.higher_is_better { change_percent < 0.0 } else { change_percent > 0.0 };
+                if worse {
+                    regressions.push(Regression {
+                        key,
+                        name: &metric_statistics.name,
+                        file_name,
+                        image: &metric_statistics.image,
+                        unit: &metric_statistics.unit,
+                        higher_is_better: metric_statistics.higher_is_better,
+                        baseline,
+                        average: stat.average,
+                        change_percent
+                    });
+                }
+            }
+        }
+
+        regressions.sort_by(|a, b| b.change_percent.abs().partial_cmp(&a.change_percent.abs()).unwrap_or(std::cmp::Ordering::Equal));
+        regressions.truncate(TOP_REGRESSIONS_AMOUNT);
+        regressions
+    }
+
+    // Metrics with their key, sorted by object, counter and name
+    pub fn sorted_stats(&self) -> Vec<(&String, &MetricStatistics)> {
+        let mut stats: Vec<(&String, &MetricStatistics)> = self.stats.iter().collect();
+        stats.sort_by(|a, b| (&a.1.object, &a.1.counter, &a.1.name).cmp(&(&b.1.object, &b.1.counter, &b.1.name)));
+        stats
+    }
+
+    pub fn get_stat(&self, metric: &str, file_name: &str) -> Option<&Stat> {
+        self.stats.get(&metric.to_string().get_file_name(""))
+            .and_then(|metric_statistics| metric_statistics.files.get(file_name))
+    }
+
+    pub fn get_correlations(&self) -> &Vec<FileCorrelations> {
+        &self.correlations
+    }
+
+    pub fn calculate_statistics(loaded_data: &LoadedData, metric_options: &MetricOptionsList, param: &Parameters) -> Statistics {
         let start = Instant::now();
         info!("Calculating statistics..");
     
-        let mut statistics: HashMap<String, HashMap<String, Stat>> = HashMap::new();
+        let mut statistics: HashMap<String, MetricStatistics> = HashMap::new();
+        let extension = param.image_format.report_extension();
     
         let distinct_metricts = loaded_data.get_distinct_metrics();
         for metric in distinct_metricts {
-            statistics.insert(metric.get_file_name(".png"), HashMap::new());
+            let mut files: HashMap<String, Stat> = HashMap::new();
+            let options = metric_options.get(&metric);
     
             let files_contain_metric = loaded_data.get_files_that_contain_metric(&metric);
             for file_data in files_contain_metric {
                 let file_values_for_metric = file_data.metrics.get(&metric).unwrap();
                 let mut values = file_values_for_metric.data.clone();
+                let seconds = file_data.get_elapsed_seconds();
+                let anomalies = detect_anomalies(&seconds, &values, &options);
+                let segments = detect_segments(&seconds, &values, &options);
+                let trend = calculate_trend(&seconds, &values, &segments);
     
                 let stat = Stat {
                     average: values.mean(),
                     median: values.median(),
+                    p1st_percentile: values.percentile(1),
                     p25th_percentile: values.percentile(25),
                     p75th_percentile: values.percentile(75),
                     p90th_percentile: values.percentile(90),
-                    p99th_percentile: values.percentile(99)
+                    p99th_percentile: values.percentile(99),
+                    trend,
+                    anomalies,
+                    segments
                 };
     
-                statistics.get_mut(&metric.get_file_name(".png")).unwrap().insert(file_data.file_name.clone(), stat);
+                files.insert(file_data.file_name.clone(), stat);
             }
+
+            let (object, counter) = split_counter_path(&metric);
+            statistics.insert(metric.get_file_name(""), MetricStatistics {
+                name: metric.clone(),
+                object,
+                counter,
+                unit: options.unit(&metric),
+                higher_is_better: options.better == Better::Higher,
+                image: metric.get_file_name(extension),
+                histogram_image: metric.get_file_name(&format!("_histogram{}", extension)),
+                cdf_image: metric.get_file_name(&format!("_cdf{}", extension)),
+                boxplot_image: metric.get_file_name(&format!("_boxplot{}", extension)),
+                files
+            });
         }
     
+        let correlations = loaded_data.get_all_data().iter()
+            .filter_map(|file_data| calculate_correlations(file_data, &param.correlation_method, extension))
+            .collect();
+
         debug!("Sequencial statistics calculation (can be parallelized): {:?}", start.elapsed());
     
         Statistics {
-            stats: statistics
+            stats: statistics,
+            correlations
         }
     }
-}
\ No newline at end of file
+}
+
+// Splits a counter path such as \\HOST\Processor(_Total)\% User Time into the performance
+// object, without its instance, and the counter. Other columns are grouped under "Other".
+fn split_counter_path(metric: &str) -> (String, String) {
+    let parts: Vec<&str> = metric.split('\\').filter(|part| !part.is_empty()).collect();
+    if parts.len() < 2 {
+        return ("Other".to_string(), metric.to_string());
+    }
+
+    let object = parts[parts.len() - 2];
+    let object = match object.find('(') {
+        Some(idx) if idx > 0 => &object[..idx],
+        _ => object
+    };
+    (object.to_string(), parts[parts.len() - 1].to_string())
+}
+
+pub fn calculate_trend(seconds: &[f64], values: &[f64], segments: &[Segment]) -> Trend {
+    let amount = seconds.len().min(values.len());
+    let flat = Trend {
+        slope: 0.0,
+        intercept: values.first().cloned().unwrap_or(0.0),
+        slope_confidence_interval: 0.0,
+        growth_per_hour: 0.0,
+        growth_per_hour_confidence_interval: 0.0,
+        significant: false
+    };
+    if amount < 3 {
+        return flat;
+    }
+
+    let n = amount as f64;
+    let mean_x = seconds[..amount].iter().sum::<f64>() / n;
+    let mean_y = values[..amount].iter().sum::<f64>() / n;
+
+    let mut sxx = 0.0;
+    let mut sxy = 0.0;
+    for (x, y) in seconds.iter().zip(values) {
+        sxx += (x - mean_x) * (x - mean_x);
+        sxy += (x - mean_x) * (y - mean_y);
+    }
+    if sxx == 0.0 {
+        return flat;
+    }
+
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+
+    let residuals: Vec<f64> = seconds.iter().zip(values)
+        .map(|(x, y)| y - (intercept + slope * x))
+        .collect();
+    let residual_sum: f64 = residuals.iter().map(|residual| residual * residual).sum();
+
+    // Counters are autocorrelated, so their samples are worth fewer independent ones. The
+    // interval is widened with the effective sample size of the lag-1 autocorrelation of the
+    // residuals.
+    let autocorrelation = if residual_sum > 0.0 {
+        residuals.windows(2).map(|pair| pair[0] * pair[1]).sum::<f64>() / residual_sum
+    } else {
+        0.0
+    };
+    let effective_n = match autocorrelation {
+        rho if rho > 0.0 => (n * (1.0 - rho) / (1.0 + rho)).max(3.0),
+        _ => n
+    };
+    let standard_error = (residual_sum / (n - 2.0) / sxx * n / effective_n).sqrt();
+    let slope_confidence_interval = t_critical_value(effective_n - 2.0, 0.95) * standard_error;
+
+    Trend {
+        slope,
+        intercept,
+        slope_confidence_interval,
+        growth_per_hour: slope * 3600.0,
+        growth_per_hour_confidence_interval: slope_confidence_interval * 3600.0,
+        significant: slope.abs() > slope_confidence_interval && !explained_by_segments(seconds, values, segments, residual_sum)
+    }
+}
+
+// Whether the levels of the change points fit the samples at least as well as the line, in which
+// case the slope comes from the steps between them rather than from a growth
+fn explained_by_segments(seconds: &[f64], values: &[f64], segments: &[Segment], residual_sum: f64) -> bool {
+    if segments.len() < 2 {
+        return false;
+    }
+
+    let mut segment_idx = 0;
+    let mut segment_residual_sum = 0.0;
+    for (x, y) in seconds.iter().zip(values) {
+        while segment_idx + 1 < segments.len() && *x > segments[segment_idx].end {
+            segment_idx += 1;
+        }
+        let residual = y - segments[segment_idx].mean;
+        segment_residual_sum += residual * residual;
+    }
+
+    segment_residual_sum <= residual_sum
+}
+
+// Two-sided critical value of the Student's t distribution, found by bisection on its cdf
+fn t_critical_value(degrees_of_freedom: f64, confidence: f64) -> f64 {
+    let distribution = StudentsT::new(0.0, 1.0, degrees_of_freedom).unwrap();
+    let target = 1.0 - (1.0 - confidence) / 2.0;
+
+    let mut low = 0.0;
+    let mut high = 1000.0;
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if distribution.cdf(middle) < target {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+
+    (low + high) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metric_options::MetricOptions;
+
+    // Deterministic noise between -1 and 1
+    fn noise(amount: usize) -> Vec<f64> {
+        let mut state: u32 = 12345;
+        (0..amount).map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as f64 / 32768.0 - 1.0
+        }).collect()
+    }
+
+    fn trend_of(values: &[f64]) -> Trend {
+        let seconds: Vec<f64> = (0..values.len()).map(|idx| idx as f64).collect();
+        let segments = detect_segments(&seconds, values, &MetricOptions::default());
+        calculate_trend(&seconds, values, &segments)
+    }
+
+    #[test]
+    fn a_growth_is_significant() {
+        let values: Vec<f64> = noise(400).iter().enumerate().map(|(idx, noise)| 10.0 + idx as f64 * 0.05 + noise).collect();
+        let trend = trend_of(&values);
+        assert!(trend.significant);
+        assert!((trend.slope - 0.05).abs() < 0.005);
+    }
+
+    #[test]
+    fn a_step_is_not_a_growth() {
+        let values: Vec<f64> = noise(400).iter().enumerate().map(|(idx, noise)| if idx < 100 { 10.0 } else { 40.0 } + noise).collect();
+        let trend = trend_of(&values);
+        assert!(trend.slope > 0.0);
+        assert!(!trend.significant);
+    }
+
+    #[test]
+    fn autocorrelated_noise_widens_the_interval() {
+        // A slow wave has strongly autocorrelated residuals
+        let wavy: Vec<f64> = (0..400).map(|idx| 10.0 + (idx as f64 / 40.0).sin()).collect();
+        let seconds: Vec<f64> = (0..400).map(|idx| idx as f64).collect();
+        assert!(!calculate_trend(&seconds, &wavy, &[]).significant);
+    }
+
+    #[test]
+    fn flat_and_short_series_have_no_trend() {
+        assert!(!trend_of(&[5.0; 50]).significant);
+        assert!(!trend_of(&[1.0, 2.0]).significant);
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs::File;
+use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use std::io::prelude::*;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use handlebars::to_json;
+use log::{debug, info};
+use serde::Serialize;
+
+use crate::data_loader::LoadedData;
+use crate::parameters::Parameters;
+use crate::statistics::Statistics;
+
+static TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3f";
+
+// Describes the run, so that the exported statistics can be told apart
+#[derive(Serialize)]
+pub struct RunMetadata {
+    pub napal_version: &'static str,
+    // When the analysis ran, in UTC
+    pub generated: String,
+    // The csv files given to Napal
+    pub inputs: Vec<String>,
+    // Tags of the run, such as build=1234
+    pub tags: BTreeMap<String, String>,
+    // Earliest and latest sample of every file
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub samples: usize,
+    pub resample_interval: Option<f64>,
+    pub files: Vec<FileMetadata>
+}
+
+#[derive(Serialize)]
+pub struct FileMetadata {
+    pub file_name: String,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub duration_seconds: f64,
+    pub samples: usize
+}
+
+impl RunMetadata {
+
+    pub fn new(loaded_data: &LoadedData, input_files: &[PathBuf], param: &Parameters) -> RunMetadata {
+        let files: Vec<FileMetadata> = loaded_data.get_all_data().iter()
+            .map(|file_data| {
+                let timestamps = &file_data.timestamps.data;
+                FileMetadata {
+                    file_name: file_data.file_name.clone(),
+                    start: timestamps.first().map(|time| time.format(TIMESTAMP_FORMAT).to_string()),
+                    end: timestamps.last().map(|time| time.format(TIMESTAMP_FORMAT).to_string()),
+                    duration_seconds: file_data.get_elapsed_seconds().last().cloned().unwrap_or(0.0),
+                    samples: timestamps.len()
+                }
+            })
+            .collect();
+
+        let first_samples = loaded_data.get_all_data().iter().filter_map(|file_data| file_data.timestamps.data.first());
+        let last_samples = loaded_data.get_all_data().iter().filter_map(|file_data| file_data.timestamps.data.last());
+        RunMetadata {
+            napal_version: env!("CARGO_PKG_VERSION"),
+            generated: Utc::now().format(TIMESTAMP_FORMAT).to_string(),
+            inputs: input_files.iter().map(|input| input.to_string_lossy().to_string()).collect(),
+            tags: param.tags.iter().cloned().collect(),
+            start: first_samples.min().map(|time| time.format(TIMESTAMP_FORMAT).to_string()),
+            end: last_samples.max().map(|time| time.format(TIMESTAMP_FORMAT).to_string()),
+            samples: files.iter().map(|file| file.samples).sum(),
+            resample_interval: param.resample.interval,
+            files
+        }
+    }
+}
+
+// Writes stats.json, with the run metadata and every statistic, and stats.csv, with a row per
+// metric and file, for dashboards and scripts
+pub fn export_statistics(loaded_data: &LoadedData, statistics: &Statistics, input_files: &[PathBuf], param: &Parameters) -> Result<()> {
+    let start = Instant::now();
+    info!("Exporting statistics..");
+    let base_path = Path::new(&param.target_directory);
+
+    let mut data = statistics.jsonify();
+    data.remove("metric_group");
+    data.remove("overview");
+    data.insert("metadata".to_string(), to_json(RunMetadata::new(loaded_data, input_files, param)));
+
+    let json_path = base_path.join("stats.json");
+    let json_content = serde_json::to_string_pretty(&data)
+        .with_context(|| "Statistics serialization problems (weird...)")?;
+    let mut json_file = File::create(&json_path)
+        .with_context(|| format!("Could not create file {:?}", &json_path))?;
+    json_file.write_all(json_content.as_bytes())
+        .with_context(|| format!("Could not write to file {:?}", json_path))?;
+
+    let csv_path = base_path.join("stats.csv");
+    let mut writer = csv::Writer::from_path(&csv_path)
+        .with_context(|| format!("Could not create file {:?}", &csv_path))?;
+    writer.write_record(["metric", "object", "counter", "unit", "file", "samples", "average", "median",
+            "p1", "p25", "p75", "p90", "p99", "growth_per_hour", "growth_per_hour_confidence_interval", "significant_trend",
+            "anomalies", "change_points"])
+        .with_context(|| format!("Could not write to file {:?}", &csv_path))?;
+    for (_, metric_statistics) in statistics.sorted_stats() {
+        let mut files: Vec<_> = metric_statistics.files.iter().collect();
+        files.sort_by(|a, b| a.0.cmp(b.0));
+        for (file_name, stat) in files {
+            let samples = loaded_data.get_all_data().iter()
+                .find(|file_data| &file_data.file_name == file_name)
+                .and_then(|file_data| file_data.metrics.get(&metric_statistics.name))
+                .map(|metric| metric.data.len())
+                .unwrap_or(0);
+
+            writer.write_record(&[
+                metric_statistics.name.clone(),
+                metric_statistics.object.clone(),
+                metric_statistics.counter.clone(),
+                metric_statistics.unit.clone(),
+                file_name.clone(),
+                samples.to_string(),
+                stat.average.to_string(),
+                stat.median.to_string(),
+                stat.p1st_percentile.to_string(),
+                stat.p25th_percentile.to_string(),
+                stat.p75th_percentile.to_string(),
+                stat.p90th_percentile.to_string(),
+                stat.p99th_percentile.to_string(),
+                stat.trend.growth_per_hour.to_string(),
+                stat.trend.growth_per_hour_confidence_interval.to_string(),
+                stat.trend.significant.to_string(),
+                stat.anomalies.len().to_string(),
+                stat.segments.len().saturating_sub(1).to_string()
+            ]).with_context(|| format!("Could not write to file {:?}", &csv_path))?;
+        }
+    }
+    writer.flush().with_context(|| format!("Could not write to file {:?}", &csv_path))?;
+
+    debug!("Sequencial statistics export: {:?}", start.elapsed());
+    Ok(())
+}
@@ -0,0 +1,422 @@
+/* Interactive metric charts of the report, drawn on a canvas from the series embedded as JSON.
+   Drag over a chart to zoom in, double click to zoom out, hover to read the values and click a
+   legend entry to hide its line. Written for the report so it works offline, without libraries. */
+(function () {
+  'use strict';
+
+  var HEIGHT = 420;
+  var MARGIN = { top: 10, right: 20, bottom: 45, left: 80 };
+  var FONT = '12px sans-serif';
+
+  // Step of about `count` ticks over the range, rounded to 1, 2 or 5 times a power of ten
+  function tickStep(range, count) {
+    var rough = range / count;
+    var power = Math.pow(10, Math.floor(Math.log(rough) / Math.LN10));
+    var fraction = rough / power;
+    var nice = fraction < 1.5 ? 1 : fraction < 3 ? 2 : fraction < 7 ? 5 : 10;
+    return nice * power;
+  }
+
+  function ticks(lower, upper, count) {
+    var step = tickStep(upper - lower, count);
+    var result = [];
+    for (var value = Math.ceil(lower / step) * step; value <= upper + step * 1e-9; value += step) {
+      result.push(Math.abs(value) < step * 1e-9 ? 0 : value);
+    }
+    return result;
+  }
+
+  function formatNumber(value) {
+    var magnitude = Math.abs(value);
+    if (value === 0) {
+      return '0';
+    }
+    if (magnitude >= 1e7 || magnitude < 1e-3) {
+      return value.toExponential(2);
+    }
+    return parseFloat(value.toPrecision(6)).toString();
+  }
+
+  var SI_PREFIXES = [[1e12, 'T'], [1e9, 'G'], [1e6, 'M'], [1e3, 'k'], [1, ''], [1e-3, 'm'], [1e-6, '\u00b5']];
+  var IEC_PREFIXES = [[1099511627776, 'Ti'], [1073741824, 'Gi'], [1048576, 'Mi'], [1024, 'Ki'], [1, '']];
+  var BASE_UNITS = { 'KB': [1024, 'B'], 'MB': [1048576, 'B'], 'GB': [1073741824, 'B'], 'KB/s': [1024, 'B/s'], 'MB/s': [1048576, 'B/s'], 'ms': [1e-3, 's'] };
+
+  // About three significant digits, without trailing zeros
+  function formatSignificant(value) {
+    var magnitude = Math.abs(value);
+    var decimals = magnitude >= 100 ? 0 : magnitude >= 10 ? 1 : magnitude >= 1 || magnitude === 0 ? 2 :
+      Math.min(9, 2 - Math.floor(Math.log(magnitude) / Math.LN10));
+    var text = String(parseFloat(value.toFixed(decimals)));
+    return text === '-0' ? '0' : text;
+  }
+
+  // Same as the formatting of the report and the images, such as 1.5 GiB, 20 ms or 12k
+  function formatUnit(value, unit) {
+    if (!isFinite(value)) {
+      return String(value);
+    }
+    if (BASE_UNITS[unit]) {
+      value *= BASE_UNITS[unit][0];
+      unit = BASE_UNITS[unit][1];
+    }
+    var prefixes = unit === '%' ? [[1, '']] : unit === '' ? SI_PREFIXES.slice(0, 5) : unit === 's' ? SI_PREFIXES.slice(4) :
+      unit.charAt(0) === 'B' ? IEC_PREFIXES : SI_PREFIXES;
+    var chosen = value === 0 ? [1, ''] : prefixes[prefixes.length - 1];
+    for (var idx = 0; idx < prefixes.length; idx++) {
+      if (Math.abs(value) >= prefixes[idx][0]) {
+        chosen = prefixes[idx];
+        break;
+      }
+    }
+
+    var number = formatSignificant(value / chosen[0]);
+    if (unit === '' || unit === '/s') {
+      return number + chosen[1] + unit;
+    }
+    return unit === '%' ? number + '%' : number + ' ' + chosen[1] + unit;
+  }
+
+  function pad(number, width) {
+    var text = String(number);
+    while (text.length < width) {
+      text = '0' + text;
+    }
+    return text;
+  }
+
+  // Clock axes show the time of day, like the images. Timestamps have no time zone, so they are
+  // read as UTC to be shown as they were recorded.
+  function formatX(series, x, span) {
+    if (series.clock_start === null) {
+      return formatNumber(x);
+    }
+    var time = new Date(series.clock_start + x * 1000);
+    var text = pad(time.getUTCHours(), 2) + ':' + pad(time.getUTCMinutes(), 2) + ':' + pad(time.getUTCSeconds(), 2);
+    if (span >= 24 * 3600) {
+      text = pad(time.getUTCMonth() + 1, 2) + '/' + pad(time.getUTCDate(), 2) + ' ' + text.substring(0, 5);
+    } else if (span < 10) {
+      text += '.' + pad(time.getUTCMilliseconds(), 3);
+    }
+    return text;
+  }
+
+  // Index of the point closest to x, the points are sorted by x
+  function nearest(points, x) {
+    var low = 0;
+    var high = points.length - 1;
+    while (high - low > 1) {
+      var middle = (low + high) >> 1;
+      if (points[middle][0] < x) {
+        low = middle;
+      } else {
+        high = middle;
+      }
+    }
+    return Math.abs(points[low][0] - x) <= Math.abs(points[high][0] - x) ? low : high;
+  }
+
+  function Chart(container, series) {
+    var self = this;
+    this.container = container;
+    this.series = series;
+    this.hidden = series.lines.map(function () { return false; });
+    this.image = container.querySelector('img');
+
+    this.full = { lower: Infinity, upper: -Infinity };
+    series.lines.forEach(function (line) {
+      if (line.points.length > 0) {
+        self.full.lower = Math.min(self.full.lower, line.points[0][0]);
+        self.full.upper = Math.max(self.full.upper, line.points[line.points.length - 1][0]);
+      }
+    });
+    if (!(self.full.upper > self.full.lower)) {
+      self.full = { lower: 0, upper: 1 };
+    }
+    this.view = { lower: this.full.lower, upper: this.full.upper };
+
+    this.canvas = document.createElement('canvas');
+    this.canvas.style.height = HEIGHT + 'px';
+    this.tooltip = document.createElement('div');
+    this.tooltip.className = 'interactive-tooltip';
+    this.tooltip.style.display = 'none';
+    this.legend = document.createElement('div');
+    this.legend.className = 'interactive-legend';
+    this.controls = document.createElement('div');
+    this.controls.className = 'interactive-controls';
+
+    container.insertBefore(this.controls, container.firstChild);
+    container.insertBefore(this.legend, container.firstChild);
+    container.insertBefore(this.tooltip, container.firstChild);
+    container.insertBefore(this.canvas, container.firstChild);
+    this.buildLegend();
+    this.buildControls();
+
+    this.dragStart = null;
+    this.hover = null;
+    this.canvas.addEventListener('mousedown', function (event) {
+      self.dragStart = self.mouseX(event);
+      event.preventDefault();
+    });
+    this.canvas.addEventListener('mousemove', function (event) {
+      self.hover = self.mouseX(event);
+      self.draw();
+    });
+    this.canvas.addEventListener('mouseleave', function () {
+      self.hover = null;
+      self.dragStart = null;
+      self.draw();
+    });
+    this.canvas.addEventListener('mouseup', function (event) {
+      var end = self.mouseX(event);
+      if (self.dragStart !== null && Math.abs(end - self.dragStart) > 5) {
+        var lower = self.toX(Math.min(self.dragStart, end));
+        var upper = self.toX(Math.max(self.dragStart, end));
+        self.view = { lower: lower, upper: upper };
+      }
+      self.dragStart = null;
+      self.draw();
+    });
+    this.canvas.addEventListener('dblclick', function () {
+      self.view = { lower: self.full.lower, upper: self.full.upper };
+      self.draw();
+    });
+    window.addEventListener('resize', function () {
+      self.draw();
+    });
+
+    this.draw();
+  }
+
+  Chart.prototype.buildLegend = function () {
+    var self = this;
+    this.series.lines.forEach(function (line, idx) {
+      var entry = document.createElement('span');
+      var swatch = document.createElement('i');
+      swatch.style.backgroundColor = line.colour;
+      entry.appendChild(swatch);
+      entry.appendChild(document.createTextNode(line.name));
+      entry.title = 'Click to hide or show the line';
+      entry.addEventListener('click', function () {
+        self.hidden[idx] = !self.hidden[idx];
+        entry.className = self.hidden[idx] ? 'hidden-line' : '';
+        self.draw();
+      });
+      self.legend.appendChild(entry);
+    });
+  };
+
+  // The image is hidden behind a link, and shown instead of the chart when scripts are disabled
+  Chart.prototype.buildControls = function () {
+    var self = this;
+    var help = document.createTextNode('Drag to zoom, double click to reset. ');
+    this.controls.appendChild(help);
+    if (!this.image) {
+      return;
+    }
+    this.image.style.display = 'none';
+    var toggle = document.createElement('a');
+    toggle.href = '#';
+    toggle.textContent = 'Show image';
+    toggle.addEventListener('click', function (event) {
+      var shown = self.image.style.display !== 'none';
+      self.image.style.display = shown ? 'none' : '';
+      toggle.textContent = shown ? 'Show image' : 'Hide image';
+      event.preventDefault();
+    });
+    this.controls.appendChild(toggle);
+  };
+
+  Chart.prototype.mouseX = function (event) {
+    var bounds = this.canvas.getBoundingClientRect();
+    var x = event.clientX - bounds.left;
+    return Math.max(MARGIN.left, Math.min(this.width - MARGIN.right, x));
+  };
+
+  Chart.prototype.toX = function (pixel) {
+    var plotWidth = this.width - MARGIN.left - MARGIN.right;
+    return this.view.lower + (pixel - MARGIN.left) / plotWidth * (this.view.upper - this.view.lower);
+  };
+
+  Chart.prototype.toPixel = function (x, y) {
+    var plotWidth = this.width - MARGIN.left - MARGIN.right;
+    var plotHeight = HEIGHT - MARGIN.top - MARGIN.bottom;
+    return [
+      MARGIN.left + (x - this.view.lower) / (this.view.upper - this.view.lower) * plotWidth,
+      MARGIN.top + (1 - (y - this.yLower) / (this.yUpper - this.yLower)) * plotHeight
+    ];
+  };
+
+  // Value range of the visible lines in the zoomed range. The whole capture starts at 0 like
+  // the images, zoomed ranges follow the values.
+  Chart.prototype.valueRange = function () {
+    var self = this;
+    var lower = Infinity;
+    var upper = -Infinity;
+    this.series.lines.forEach(function (line, idx) {
+      if (self.hidden[idx]) {
+        return;
+      }
+      line.points.forEach(function (point) {
+        if (point[0] >= self.view.lower && point[0] <= self.view.upper) {
+          lower = Math.min(lower, point[1]);
+          upper = Math.max(upper, point[1]);
+        }
+      });
+    });
+    if (lower > upper) {
+      return [0, 1];
+    }
+    var zoomed = this.view.lower > this.full.lower || this.view.upper < this.full.upper;
+    if (!zoomed) {
+      lower = Math.min(lower, 0);
+    }
+    if (upper === lower) {
+      upper = lower + 1;
+    }
+    var padding = zoomed ? (upper - lower) * 0.05 : 0;
+    return [lower - padding, upper + padding];
+  };
+
+  Chart.prototype.draw = function () {
+    var self = this;
+    var ratio = window.devicePixelRatio || 1;
+    this.width = this.canvas.clientWidth || 800;
+    this.canvas.width = this.width * ratio;
+    this.canvas.height = HEIGHT * ratio;
+    var context = this.canvas.getContext('2d');
+    context.setTransform(ratio, 0, 0, ratio, 0, 0);
+    context.clearRect(0, 0, this.width, HEIGHT);
+    context.font = FONT;
+
+    var range = this.valueRange();
+    this.yLower = range[0];
+    this.yUpper = range[1];
+    var right = this.width - MARGIN.right;
+    var bottom = HEIGHT - MARGIN.bottom;
+
+    // Grid and axes
+    context.strokeStyle = '#e0e0e0';
+    context.fillStyle = '#212529';
+    context.lineWidth = 1;
+    context.textAlign = 'right';
+    context.textBaseline = 'middle';
+    ticks(this.yLower, this.yUpper, 8).forEach(function (value) {
+      var y = self.toPixel(self.view.lower, value)[1];
+      context.beginPath();
+      context.moveTo(MARGIN.left, y);
+      context.lineTo(right, y);
+      context.stroke();
+      context.fillText(formatUnit(value, self.series.unit), MARGIN.left - 6, y);
+    });
+    var span = this.view.upper - this.view.lower;
+    context.textAlign = 'center';
+    context.textBaseline = 'top';
+    ticks(this.view.lower, this.view.upper, Math.max(2, Math.floor(this.width / 120))).forEach(function (value) {
+      var x = self.toPixel(value, self.yLower)[0];
+      context.beginPath();
+      context.moveTo(x, MARGIN.top);
+      context.lineTo(x, bottom);
+      context.stroke();
+      context.fillText(formatX(self.series, value, span), x, bottom + 6);
+    });
+    context.fillText(this.series.x_label, MARGIN.left + (right - MARGIN.left) / 2, bottom + 26);
+    context.strokeStyle = '#000000';
+    context.strokeRect(MARGIN.left, MARGIN.top, right - MARGIN.left, bottom - MARGIN.top);
+
+    // Lines, clipped to the plotting area
+    context.save();
+    context.beginPath();
+    context.rect(MARGIN.left, MARGIN.top, right - MARGIN.left, bottom - MARGIN.top);
+    context.clip();
+    context.lineWidth = 1.5;
+    context.lineJoin = 'round';
+    this.series.lines.forEach(function (line, idx) {
+      if (self.hidden[idx] || line.points.length === 0) {
+        return;
+      }
+      context.strokeStyle = line.colour;
+      context.setLineDash(line.dash ? line.dash : []);
+      context.beginPath();
+      line.points.forEach(function (point, pointIdx) {
+        var pixel = self.toPixel(point[0], point[1]);
+        if (pointIdx === 0) {
+          context.moveTo(pixel[0], pixel[1]);
+        } else {
+          context.lineTo(pixel[0], pixel[1]);
+        }
+      });
+      context.stroke();
+    });
+    context.restore();
+    context.setLineDash([]);
+
+    // Zoom selection
+    if (this.dragStart !== null && this.hover !== null) {
+      context.fillStyle = 'rgba(0, 123, 255, 0.15)';
+      context.fillRect(Math.min(this.dragStart, this.hover), MARGIN.top, Math.abs(this.hover - this.dragStart), bottom - MARGIN.top);
+    }
+
+    this.drawHover(context, bottom);
+  };
+
+  // Vertical line at the mouse, with the value of every visible line at that moment
+  Chart.prototype.drawHover = function (context, bottom) {
+    var self = this;
+    if (this.hover === null) {
+      this.tooltip.style.display = 'none';
+      return;
+    }
+
+    context.strokeStyle = '#6c757d';
+    context.beginPath();
+    context.moveTo(this.hover, MARGIN.top);
+    context.lineTo(this.hover, bottom);
+    context.stroke();
+
+    var x = this.toX(this.hover);
+    var rows = [];
+    this.series.lines.forEach(function (line, idx) {
+      if (self.hidden[idx] || line.points.length === 0) {
+        return;
+      }
+      var point = line.points[nearest(line.points, x)];
+      var pixel = self.toPixel(point[0], point[1]);
+      context.fillStyle = line.colour;
+      context.beginPath();
+      context.arc(pixel[0], pixel[1], 3, 0, 2 * Math.PI);
+      context.fill();
+      rows.push({ colour: line.colour, text: line.name + ': ' + formatUnit(point[1], self.series.unit) });
+    });
+
+    this.tooltip.textContent = '';
+    var header = document.createElement('div');
+    header.textContent = formatX(this.series, x, this.view.upper - this.view.lower);
+    this.tooltip.appendChild(header);
+    rows.forEach(function (row) {
+      var entry = document.createElement('div');
+      entry.style.color = row.colour;
+      entry.textContent = row.text;
+      self.tooltip.appendChild(entry);
+    });
+    this.tooltip.style.display = 'block';
+    var left = this.hover + 12;
+    if (left + this.tooltip.offsetWidth > this.width) {
+      left = this.hover - 12 - this.tooltip.offsetWidth;
+    }
+    this.tooltip.style.left = left + 'px';
+    this.tooltip.style.top = MARGIN.top + 'px';
+  };
+
+  var data = document.getElementById('series-data');
+  if (!data) {
+    return;
+  }
+  var allSeries = JSON.parse(data.textContent);
+  var containers = document.querySelectorAll('.interactive-chart');
+  for (var idx = 0; idx < containers.length; idx++) {
+    var series = allSeries[containers[idx].getAttribute('data-series')];
+    if (series) {
+      new Chart(containers[idx], series);
+    }
+  }
+})();
@@ -0,0 +1,82 @@
+/* Metric filter and sortable tables of the report. Without scripts every metric and row is
+   still shown, in the order of the report. */
+(function () {
+  'use strict';
+
+  // Hides the index entries and overview rows whose name misses any of the typed words,
+  // and the groups left empty
+  function filterMetrics(text) {
+    var words = text.toLowerCase().split(/\s+/).filter(function (word) { return word.length > 0; });
+    var entries = document.querySelectorAll('[data-filter]');
+    for (var idx = 0; idx < entries.length; idx++) {
+      var name = entries[idx].getAttribute('data-filter').toLowerCase();
+      var shown = words.every(function (word) { return name.indexOf(word) !== -1; });
+      entries[idx].style.display = shown ? '' : 'none';
+    }
+
+    var groups = document.querySelectorAll('.metric-group');
+    for (var groupIdx = 0; groupIdx < groups.length; groupIdx++) {
+      var items = groups[groupIdx].querySelectorAll('li');
+      var anyShown = false;
+      for (var itemIdx = 0; itemIdx < items.length; itemIdx++) {
+        anyShown = anyShown || items[itemIdx].style.display !== 'none';
+      }
+      groups[groupIdx].style.display = anyShown ? '' : 'none';
+    }
+  }
+
+  // Cells that start with a number are compared as numbers, the others as text
+  function compareCells(a, b) {
+    var numberA = parseFloat(a);
+    var numberB = parseFloat(b);
+    if (!isNaN(numberA) && !isNaN(numberB)) {
+      return numberA - numberB;
+    }
+    return a.localeCompare(b);
+  }
+
+  // Formatted values keep the raw number in data-value, so 900 MiB sorts below 1.5 GiB
+  function cellValue(cell) {
+    var value = cell.getAttribute('data-value');
+    return value !== null ? value : cell.textContent.trim();
+  }
+
+  function sortTable(table, header, column) {
+    var body = table.tBodies[0];
+    var descending = header.className === 'sorted-ascending';
+    var headers = table.tHead.rows[0].cells;
+    for (var idx = 0; idx < headers.length; idx++) {
+      headers[idx].className = '';
+    }
+    header.className = descending ? 'sorted-descending' : 'sorted-ascending';
+
+    var rows = Array.prototype.slice.call(body.rows);
+    rows.sort(function (rowA, rowB) {
+      var order = compareCells(cellValue(rowA.cells[column]), cellValue(rowB.cells[column]));
+      return descending ? -order : order;
+    });
+    rows.forEach(function (row) {
+      body.appendChild(row);
+    });
+  }
+
+  var tables = document.querySelectorAll('table.sortable');
+  Array.prototype.forEach.call(tables, function (table) {
+    if (!table.tHead || table.tBodies.length === 0) {
+      return;
+    }
+    Array.prototype.forEach.call(table.tHead.rows[0].cells, function (header, column) {
+      header.title = 'Click to sort';
+      header.addEventListener('click', function () {
+        sortTable(table, header, column);
+      });
+    });
+  });
+
+  var search = document.getElementById('metric-search');
+  if (search) {
+    search.addEventListener('input', function () {
+      filterMetrics(search.value);
+    });
+  }
+})();